@@ -0,0 +1,92 @@
+use crate::error::Location;
+use crate::grammar_util::{erase_comments, Literals, Spans};
+use crate::program::*;
+use crate::type_printer::TypePrinter;
+
+use std::collections::HashSet;
+
+/// A position in the source at which the annotator inserts inferred types.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Site {
+    /// The type parameters `<a, b>` of a polymorphic function statement after the function name.
+    TypeParameters(FunctionNode, StmtNode),
+    /// An omitted `: type` annotation.
+    Annotation(OptTypeNode),
+}
+
+/// Returns the arg lists of function type annotations such as `(x) => number`. Arguments in type
+/// annotations don't have inferred types.
+fn type_annotation_args(p: &Program) -> HashSet<OptTypeNode> {
+    let mut result = HashSet::new();
+    for (_, mut args, _) in p.iter_function_type_node() {
+        while let Some((_, _, otn, tail)) = p
+            .iter_cons_arg_list_node()
+            .find(|(args0, _, _, _)| *args0 == args)
+        {
+            result.insert(otn);
+            args = tail;
+        }
+    }
+    result
+}
+
+/// Re-emits the source of a well-typed module with all omitted type annotations of `let`
+/// statements, function arguments and function return types filled in, e.g. `let x = 5;` becomes
+/// `let x: number = 5;`. Polymorphic functions receive type parameters, e.g. `function id<a>(y:
+/// a): a`.
+///
+/// `p`, `lits` and `spans` must be the results of checking `src`. Comments in `src` are preserved.
+pub fn annotate(src: &str, p: &Program, lits: &Literals, spans: &Spans) -> String {
+    let type_annotation_args = type_annotation_args(p);
+    let mut sites: Vec<(usize, Site)> = p
+        .iter_none_opt_type_node()
+        .filter(|otn| !type_annotation_args.contains(otn))
+        .map(|otn| (spans.opt_types[&otn].begin, Site::Annotation(otn)))
+        .collect();
+    sites.extend(p.iter_function_stmt_node().map(|(stmt, func)| {
+        let name_end = spans.function_names[&func].end;
+        (name_end, Site::TypeParameters(func, stmt))
+    }));
+    // We render types in source order, so that type variables are named in the order in which they
+    // appear in the output.
+    sites.sort();
+
+    let mut printer = TypePrinter::new(p, lits).with_positional_arg_names();
+    let no_comments_src = erase_comments(src);
+    let mut insertions: Vec<(Location, String)> = Vec::new();
+    for (offset, site) in sites {
+        let text = match site {
+            Site::TypeParameters(func, stmt) => {
+                let sigma = p.function_node_type(func).unwrap();
+                let ctx = p.stmt_type_context(stmt).unwrap();
+                printer.quantifiers(sigma, ctx)
+            }
+            Site::Annotation(otn) => {
+                let sigma = p.semantic_opt_type(otn).unwrap();
+                format!(": {}", printer.print(sigma))
+            }
+        };
+        if !text.is_empty() {
+            let location = Location::from_offset(&no_comments_src, offset);
+            insertions.push((location, text));
+        }
+    }
+
+    // Erasing comments only removes suffixes of lines, so locations in the source without comments
+    // are also valid in the original source.
+    let mut result = String::new();
+    let mut insertions = insertions.into_iter().peekable();
+    for (line_index, line) in src.split('\n').enumerate() {
+        if line_index > 0 {
+            result.push('\n');
+        }
+        let mut column = 0;
+        while let Some((location, text)) = insertions.next_if(|(loc, _)| loc.line == line_index) {
+            result.push_str(&line[column..location.column]);
+            result.push_str(&text);
+            column = location.column;
+        }
+        result.push_str(&line[column..]);
+    }
+    result
+}
@@ -0,0 +1,127 @@
+use crate::annotate::annotate;
+use crate::check_source;
+
+use indoc::indoc;
+
+fn annotated(src: &str) -> String {
+    let (p, lits, spans, _) = check_source(src).unwrap();
+    annotate(src, &p, &lits, &spans)
+}
+
+#[test]
+fn annotate_let() {
+    let src = indoc! {"
+        let x = 5;
+        let y: string = 'abc';
+        let z = x == 3;
+    "};
+    assert_eq!(
+        annotated(src),
+        indoc! {"
+            let x: number = 5;
+            let y: string = 'abc';
+            let z: boolean = x == 3;
+        "}
+    );
+}
+
+#[test]
+fn annotate_function() {
+    let src = indoc! {"
+        function is_five(n) {
+            return n == 5;
+        }
+        function log(s: string, t): void {
+            is_five(t);
+        }
+    "};
+    assert_eq!(
+        annotated(src),
+        indoc! {"
+            function is_five(n: number): boolean {
+                return n == 5;
+            }
+            function log(s: string, t: number): void {
+                is_five(t);
+            }
+        "}
+    );
+}
+
+#[test]
+fn annotate_polymorphic_function() {
+    let src = indoc! {"
+        function apply(f, x) {
+            let y = f(x);
+            return y;
+        }
+        let b = apply(function not(c) { return c == false; }, true);
+    "};
+    assert_eq!(
+        annotated(src),
+        indoc! {"
+            function apply<a, b>(f: (arg0: a) => b, x: a): b {
+                let y: b = f(x);
+                return y;
+            }
+            let b: boolean = apply(function not(c: boolean): boolean { return c == false; }, true);
+        "}
+    );
+}
+
+#[test]
+fn annotate_row_polymorphic_function() {
+    let src = indoc! {"
+        function getName(o) {
+            const name = o.name;
+            return name;
+        }
+        let n = getName({ name: 'n' });
+    "};
+    assert_eq!(
+        annotated(src),
+        indoc! {"
+            function getName<a extends { name: b }, b>(o: a): b {
+                const name: b = o.name;
+                return name;
+            }
+            let n: string = getName({ name: 'n' });
+        "}
+    );
+}
+
+#[test]
+fn annotate_preserves_comments() {
+    let src = indoc! {"
+        // The identity function.
+        function id(x) { // Returns its argument.
+            return x;
+        }
+    "};
+    assert_eq!(
+        annotated(src),
+        indoc! {"
+            // The identity function.
+            function id<a>(x: a): a { // Returns its argument.
+                return x;
+            }
+        "}
+    );
+}
+
+#[test]
+fn annotate_skips_function_type_annotation_args() {
+    let src = indoc! {"
+        function call(f: (x) => number) {
+            return f(1);
+        }
+    "};
+    assert_eq!(
+        annotated(src),
+        indoc! {"
+            function call(f: (x) => number): number {
+                return f(1);
+            }
+        "}
+    );
+}
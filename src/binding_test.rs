@@ -1,4 +1,5 @@
 use crate::check_source;
+use crate::error::display_diagnostics;
 
 use indoc::indoc;
 
@@ -9,7 +10,10 @@ fn bad_undeclared_variable() {
         a();
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Usage of undeclared variable");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Usage of undeclared variable `a` at 2:1"
+    );
 }
 
 #[test]
@@ -23,7 +27,10 @@ fn bad_undeclared_variable_in_app() {
         asdf(x, y);
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Usage of undeclared variable");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Usage of undeclared variable `y` at 6:9"
+    );
 }
 
 #[test]
@@ -33,7 +40,10 @@ fn bad_duplicate_let_let() {
         let k = 6;
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Variable declared more than once");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `k` declared more than once at 2:1, previously declared at 1:1"
+    );
 }
 
 #[test]
@@ -43,7 +53,10 @@ fn bad_duplicate_function_function() {
         function k () {}
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Variable declared more than once");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `k` declared more than once at 2:1, previously declared at 1:1"
+    );
 }
 
 #[test]
@@ -53,7 +66,10 @@ fn bad_duplicate_let_function() {
         function k () {}
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Variable declared more than once");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `k` declared more than once at 2:1, previously declared at 1:1"
+    );
 }
 
 #[test]
@@ -63,7 +79,10 @@ fn bad_duplicate_function_let() {
         let k = 5;
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Variable declared more than once");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `k` declared more than once at 2:1, previously declared at 1:1"
+    );
 }
 
 #[test]
@@ -73,7 +92,10 @@ fn bad_duplicate_let_function_arg() {
         function asdf (k: number) {}
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Variable declared more than once");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `k` declared more than once at 2:16, previously declared at 1:1"
+    );
 }
 
 #[test]
@@ -82,7 +104,10 @@ fn bad_duplicate_function_name_arg() {
         function k (k: number) {}
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Variable declared more than once");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `k` declared more than once at 1:13, previously declared at 1:1"
+    );
 }
 
 #[test]
@@ -91,7 +116,10 @@ fn bad_duplicate_function_arg_arg() {
         function asdf (k: number, k: number) {}
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Variable declared more than once");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `k` declared more than once at 1:27, previously declared at 1:16"
+    );
 }
 
 #[test]
@@ -103,7 +131,10 @@ fn bad_duplicate_ambient_function_local() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Variable declared more than once");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `k` declared more than once at 3:5, previously declared at 1:1"
+    );
 }
 
 #[test]
@@ -114,7 +145,10 @@ fn bad_duplicate_function_name_local() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Variable declared more than once");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `k` declared more than once at 2:5, previously declared at 1:1"
+    );
 }
 
 #[test]
@@ -125,7 +159,10 @@ fn bad_duplicate_function_arg_local() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Variable declared more than once");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `k` declared more than once at 2:5, previously declared at 1:16"
+    );
 }
 
 #[test]
@@ -140,7 +177,10 @@ fn bad_duplicate_nested_if_true() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Variable declared more than once");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `k` declared more than once at 4:9, previously declared at 2:5"
+    );
 }
 
 #[test]
@@ -155,7 +195,10 @@ fn bad_duplicate_nested_if_false() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Variable declared more than once");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `k` declared more than once at 5:9, previously declared at 2:5"
+    );
 }
 
 #[test]
@@ -169,7 +212,10 @@ fn bad_duplicate_nested_while() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Variable declared more than once");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `k` declared more than once at 4:9, previously declared at 2:5"
+    );
 }
 
 #[test]
@@ -181,7 +227,10 @@ fn bad_duplicate_nested_function_expr_name() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Variable declared more than once");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `k` declared more than once at 3:6, previously declared at 2:5"
+    );
 }
 
 #[test]
@@ -193,7 +242,10 @@ fn bad_duplicate_nested_function_expr_arg() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Variable declared more than once");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `k` declared more than once at 3:20, previously declared at 2:5"
+    );
 }
 
 #[test]
@@ -207,7 +259,10 @@ fn bad_duplicate_nested_function_expr_body() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Variable declared more than once");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `k` declared more than once at 4:9, previously declared at 2:5"
+    );
 }
 
 #[test]
@@ -226,5 +281,231 @@ fn bad_duplicate_deeply_nested() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Variable declared more than once");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `k` declared more than once at 6:17, previously declared at 1:1"
+    );
+}
+
+#[test]
+fn bad_duplicate_previous_declaration_in_scope() {
+    let err = check_source(&indoc! {"
+        function foo (k: number) {}
+        function bar (k: number) {
+            let k = 5;
+        }
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `k` declared more than once at 3:5, previously declared at 2:15"
+    );
+}
+
+#[test]
+fn bad_duplicate_const_let() {
+    let err = check_source(&indoc! {"
+        const k = 5;
+        let k = 6;
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `k` declared more than once at 2:1, previously declared at 1:1"
+    );
+}
+
+#[test]
+fn bad_assign_to_const() {
+    let err = check_source(&indoc! {"
+        const k: number = 5;
+        function foo() {
+            k = k + 1;
+        }
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Cannot assign to constant `k` at 3:5, declared at 1:1"
+    );
+}
+
+#[test]
+fn assign_to_let_named_like_later_const() {
+    check_source(&indoc! {"
+        function foo() {
+            let k = 'abc';
+            k = 'def';
+        }
+        const k = 5;
+    "})
+    .unwrap();
+}
+
+#[test]
+fn bad_for_variable_after_loop() {
+    let err = check_source(&indoc! {"
+        for (let i = 0; i < 3; i = i + 1) {
+            i;
+        }
+        i = 3;
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Usage of undeclared variable `i` at 4:1"
+    );
+}
+
+#[test]
+fn bad_for_variable_shadowing() {
+    let err = check_source(&indoc! {"
+        let i = 0;
+        for (let i = 0; i < 3; i = i + 1) {}
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `i` declared more than once at 2:6, previously declared at 1:1"
+    );
+}
+
+#[test]
+fn for_variables_in_sibling_loops() {
+    check_source(&indoc! {"
+        for (let i = 0; i < 3; i = i + 1) {}
+        for (const i = 0; i < 3;) {}
+    "})
+    .unwrap();
+}
+
+#[test]
+fn bad_for_of_variable_scope() {
+    let err = check_source(&indoc! {"
+        let xs = [1, 2];
+        for (const x of xs) {
+            x = 3;
+        }
+        for (let y of [x]) {
+            y = 3;
+        }
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Cannot assign to constant `x` at 3:5, declared at 2:1
+            Usage of undeclared variable `x` at 5:16"}
+    );
+}
+
+#[test]
+fn bad_for_of_variable_shadowing() {
+    let err = check_source(&indoc! {"
+        let x = 1;
+        for (const x of [2]) {}
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Variable `x` declared more than once at 2:1, previously declared at 1:1"
+    );
+}
+
+#[test]
+fn bad_undeclared_type() {
+    let err = check_source(&indoc! {"
+        type Point = { x: number; y: number };
+        let p: Pointt = { x: 1, y: 2 };
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Usage of undeclared type `Pointt` at 2:8"
+    );
+}
+
+#[test]
+fn bad_type_alias_scope() {
+    let err = check_source(&indoc! {"
+        function f(x: Num) {
+            type Num = number;
+            let y: Num = x;
+            return y;
+        }
+        let z: Num = 5;
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Usage of undeclared type `Num` at 1:15
+            Usage of undeclared type `Num` at 6:8"}
+    );
+}
+
+#[test]
+fn bad_forward_type_reference() {
+    let err = check_source(&indoc! {"
+        type A = B[];
+        type B = number;
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Usage of undeclared type `B` at 1:10"
+    );
+}
+
+#[test]
+fn bad_duplicate_type_alias() {
+    let err = check_source(&indoc! {"
+        type T = number;
+        type T = string;
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Type `T` declared more than once at 2:1, previously declared at 1:1"
+    );
+}
+
+#[test]
+fn bad_duplicate_nested_type_alias() {
+    let err = check_source(&indoc! {"
+        type T = number;
+        function f() {
+            type T = string;
+        }
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Type `T` declared more than once at 3:5, previously declared at 1:1"
+    );
+}
+
+#[test]
+fn bad_recursive_type_alias() {
+    let err = check_source(&indoc! {"
+        type List = { head: number; tail: List[] };
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Type alias `List` refers to itself at 1:35"
+    );
+}
+
+#[test]
+fn type_names_separate_from_variables() {
+    check_source(&indoc! {"
+        type x = number;
+        let x: x = 5;
+        function f(y: x) {
+            type y = string;
+            return y;
+        }
+    "})
+    .unwrap();
 }
@@ -0,0 +1,32 @@
+use crate::grammar_util::Literals;
+use crate::program::*;
+use crate::type_printer::{module_bindings, BindingKind, TypePrinter};
+
+/// Renders a TypeScript declaration file (`.d.ts`) which declares the top-level bindings of a
+/// module with their inferred types, e.g. `declare function id<a>(y: a): a;`.
+pub fn declaration_file(module: ModuleNode, p: &Program, lits: &Literals) -> String {
+    let mut result = String::new();
+    for binding in module_bindings(module, p, lits) {
+        let name = lits.var_name(binding.var);
+        let mut printer = TypePrinter::new(p, lits).with_positional_arg_names();
+        let arg_names = binding.arg_names.as_deref();
+        let declaration = match binding.kind {
+            BindingKind::Let => {
+                let ty = printer.print_general(binding.ty, binding.ctx, arg_names);
+                format!("declare let {name}: {ty};")
+            }
+            BindingKind::Const => {
+                let ty = printer.print_general(binding.ty, binding.ctx, arg_names);
+                format!("declare const {name}: {ty};")
+            }
+            BindingKind::Function => {
+                let arg_names = arg_names.expect("function binding should have argument names");
+                let signature = printer.print_signature(binding.ty, binding.ctx, arg_names);
+                format!("declare function {name}{signature};")
+            }
+        };
+        result.push_str(&declaration);
+        result.push('\n');
+    }
+    result
+}
@@ -0,0 +1,94 @@
+use crate::check_source;
+use crate::declaration_file::declaration_file;
+
+use indoc::indoc;
+
+fn declarations(src: &str) -> String {
+    let (p, lits, _, module) = check_source(src).unwrap();
+    declaration_file(module, &p, &lits)
+}
+
+#[test]
+fn declare_let() {
+    let dts = declarations(indoc! {"
+        let k: number = 5;
+        let s = 'xyz';
+    "});
+    assert_eq!(
+        dts,
+        indoc! {"
+            declare let k: number;
+            declare let s: string;
+        "}
+    );
+}
+
+#[test]
+fn declare_const() {
+    let dts = declarations(indoc! {"
+        const k: number = 5;
+        const s = 'xyz';
+    "});
+    assert_eq!(
+        dts,
+        indoc! {"
+            declare const k: number;
+            declare const s: string;
+        "}
+    );
+}
+
+#[test]
+fn declare_polymorphic_function() {
+    let dts = declarations(indoc! {"
+        function id(y) {
+            return y;
+        }
+        function apply(f, x) {
+            return f(x);
+        }
+        let the_five = apply(id, 5);
+    "});
+    assert_eq!(
+        dts,
+        indoc! {"
+            declare function id<a>(y: a): a;
+            declare function apply<a, b>(f: (arg0: a) => b, x: a): b;
+            declare let the_five: number;
+        "}
+    );
+}
+
+#[test]
+fn declare_function_literal() {
+    let dts = declarations(indoc! {"
+        let is_abc = function is_abc_impl(s: string): boolean {
+            return s == 'abc';
+        };
+        function foo() {}
+    "});
+    assert_eq!(
+        dts,
+        indoc! {"
+            declare let is_abc: (s: string) => boolean;
+            declare function foo(): void;
+        "}
+    );
+}
+
+#[test]
+fn declare_row_polymorphic_function() {
+    let dts = declarations(indoc! {"
+        function getName(o) {
+            return o.name;
+        }
+        const alice = { name: 'Alice', age: 30 };
+    "});
+    assert_eq!(
+        dts,
+        indoc! {"
+            declare function getName<a extends { name: b }, b>(o: a): b;
+            declare const alice: { age: number; name: string };
+        "}
+    );
+}
@@ -0,0 +1,178 @@
+use crate::grammar_util::{Span, Spans};
+use crate::program::*;
+
+use std::collections::{HashMap, HashSet};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DeclarationKind {
+    Let,
+    Const,
+    Function,
+    Arg,
+}
+
+/// A node that introduces a variable, i.e. a `let` or `const` statement, a `for...of` statement, a
+/// function or a function argument.
+#[derive(Copy, Clone, Debug)]
+pub struct Declaration {
+    pub kind: DeclarationKind,
+    pub var: Var,
+    /// The span of the node that introduces the variable.
+    pub span: Span,
+    /// The span of source in which the variable is in scope.
+    pub scope: Span,
+}
+
+/// Collects all variable declarations in a parsed program.
+///
+/// The scopes of the declarations mirror the VarTypeInX axioms in program.eqlog: A variable
+/// introduced by a `let` or `const` statement is in scope for the statements after the `let`
+/// statement in the same block (or, in the header of a `for` loop, for the rest of the loop), the
+/// variable of a `for...of` statement is in scope for the body of the loop, the name of a function
+/// is in scope within the function and, for function statements, after the function statement,
+/// and a function argument is in scope for the subsequent arguments and the body of the function.
+pub fn declarations(p: &Program, spans: &Spans) -> Vec<Declaration> {
+    let stmt_lists: HashMap<StmtNode, StmtListNode> = p
+        .iter_cons_stmt_list_node()
+        .map(|(stmts, head, _)| (head, stmts))
+        .collect();
+    let function_stmts: HashMap<FunctionNode, StmtNode> = p
+        .iter_function_stmt_node()
+        .map(|(stmt, func)| (func, stmt))
+        .collect();
+    let cons_args: HashMap<ArgListNode, (Var, ArgListNode)> = p
+        .iter_cons_arg_list_node()
+        .map(|(al, var, _, tail)| (al, (var, tail)))
+        .collect();
+    let const_stmts: HashSet<StmtNode> = p
+        .iter_const_stmt_node()
+        .map(|(stmt, _, _, _)| stmt)
+        .chain(
+            p.iter_const_for_of_stmt_node()
+                .map(|(stmt, _, _, _, _)| stmt),
+        )
+        .collect();
+
+    let mut decls = Vec::new();
+
+    for (stmt, var, _, _) in p.iter_let_stmt_node() {
+        // Statements in the header of a `for` loop with a syntax error are not part of any
+        // statement list.
+        let Some(stmts) = stmt_lists.get(&stmt) else {
+            continue;
+        };
+        let span = spans.stmts[&stmt];
+        let block_end = spans.stmt_lists[stmts].end;
+        decls.push(Declaration {
+            kind: match const_stmts.contains(&stmt) {
+                true => DeclarationKind::Const,
+                false => DeclarationKind::Let,
+            },
+            var,
+            span,
+            scope: Span::new(span.end, block_end),
+        });
+    }
+
+    for (stmt, var, _, iterable, _) in p.iter_for_of_stmt_node() {
+        let span = spans.stmts[&stmt];
+        decls.push(Declaration {
+            kind: match const_stmts.contains(&stmt) {
+                true => DeclarationKind::Const,
+                false => DeclarationKind::Let,
+            },
+            var,
+            span,
+            scope: Span::new(spans.exprs[&iterable].end, span.end),
+        });
+    }
+
+    for (func, var, args, _, _) in p.iter_function() {
+        let span = spans.functions[&func];
+        let scope_end = match function_stmts.get(&func) {
+            Some(stmt) => spans.stmt_lists[&stmt_lists[stmt]].end,
+            None => span.end,
+        };
+        decls.push(Declaration {
+            kind: DeclarationKind::Function,
+            var,
+            span,
+            scope: Span::new(span.begin, scope_end),
+        });
+
+        let mut al = args;
+        while let Some((arg_var, tail)) = cons_args.get(&al) {
+            let arg_span = spans.args[&al];
+            decls.push(Declaration {
+                kind: DeclarationKind::Arg,
+                var: *arg_var,
+                span: arg_span,
+                scope: Span::new(arg_span.end, span.end),
+            });
+            al = *tail;
+        }
+    }
+
+    decls
+}
+
+/// Returns the innermost declaration of `var` which is in scope at byte offset `offset` and which
+/// begins before `offset`.
+pub fn resolve(decls: &[Declaration], var: Var, offset: usize) -> Option<&Declaration> {
+    decls
+        .iter()
+        .filter(|decl| decl.var == var && decl.span.begin < offset)
+        .filter(|decl| decl.scope.begin <= offset && offset < decl.scope.end)
+        .max_by_key(|decl| decl.scope.begin)
+}
+
+/// A `type` statement, which introduces a type alias.
+#[derive(Copy, Clone, Debug)]
+pub struct TypeAliasDeclaration {
+    pub name: TypeName,
+    /// The type node that the alias stands for.
+    pub definition: TypeNode,
+    /// The span of the `type` statement.
+    pub span: Span,
+    /// The span of source in which the type name is in scope.
+    pub scope: Span,
+}
+
+/// Collects all type alias declarations in a parsed program.
+///
+/// A type alias is in scope from its `type` statement to the end of the block containing the
+/// statement. In particular, it is in scope within its own definition, so that recursive aliases
+/// can be detected. Like variables, type aliases must be declared before they are used, so the
+/// definition of an alias can't refer to aliases declared after it.
+pub fn type_alias_declarations(p: &Program, spans: &Spans) -> Vec<TypeAliasDeclaration> {
+    let stmt_lists: HashMap<StmtNode, StmtListNode> = p
+        .iter_cons_stmt_list_node()
+        .map(|(stmts, head, _)| (head, stmts))
+        .collect();
+    p.iter_type_alias_stmt_node()
+        .filter_map(|(stmt, name, definition)| {
+            let stmts = stmt_lists.get(&stmt)?;
+            let span = spans.stmts[&stmt];
+            Some(TypeAliasDeclaration {
+                name,
+                definition,
+                span,
+                scope: Span::new(span.begin, spans.stmt_lists[stmts].end),
+            })
+        })
+        .collect()
+}
+
+/// Returns the innermost declaration of the type alias `name` which is in scope at byte offset
+/// `offset` and which begins before `offset`.
+pub fn resolve_type_name(
+    decls: &[TypeAliasDeclaration],
+    name: TypeName,
+    offset: usize,
+) -> Option<&TypeAliasDeclaration> {
+    decls
+        .iter()
+        .filter(|decl| decl.name == name && decl.span.begin < offset)
+        .filter(|decl| decl.scope.begin <= offset && offset < decl.scope.end)
+        .max_by_key(|decl| decl.scope.begin)
+}
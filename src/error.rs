@@ -1,74 +1,366 @@
 use std::fmt;
 
-use crate::grammar_util::NeverType;
+use crate::grammar_util::{NeverType, Span};
+use lalrpop_util::lexer::Token;
 
+/// Line and column (both 0-based) of a position in the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    /// Computes the location of a byte offset into `src`.
+    pub fn from_offset(src: &str, offset: usize) -> Self {
+        let consumed_src = &src[..offset];
+        let line_index: usize = consumed_src.matches('\n').count();
+        let line_begin: usize = consumed_src.rfind('\n').map_or(0, |i| i + 1);
+        Location {
+            line: line_index,
+            column: offset - line_begin,
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let line_number = self.line + 1;
+        let column_number = self.column + 1;
+        write!(f, "{line_number}:{column_number}")
+    }
+}
+
+/// An error in a module. Checking a module results in a list of diagnostics ordered by location.
 #[derive(Debug, Clone)]
-pub enum LanguageError {
-    /// Line and column (both 0-based) of a parse error.
-    ParseError { line: usize, column: usize },
-    /// A variable is declared more than once for the same scope.
-    VariableShadowing,
+pub enum Diagnostic {
+    /// A parse error. `expected` lists the tokens that would have been valid at `location`, and
+    /// `found` is the token that was found instead, or `None` at the end of the source.
+    ParseError {
+        location: Location,
+        expected: Vec<String>,
+        found: Option<String>,
+    },
+    /// A variable is declared more than once for the same scope. `location` is the location of the
+    /// second declaration, and `previous` the location of the declaration it collides with, if it
+    /// could be determined.
+    VariableShadowing {
+        name: String,
+        location: Location,
+        previous: Option<Location>,
+    },
     /// A variable is used without prior declaration.
-    UndeclaredVariable,
+    UndeclaredVariable { name: String, location: Location },
+    /// A type alias is declared more than once for the same scope. `location` is the location of
+    /// the second `type` statement, and `previous` the location of the one it collides with.
+    TypeAliasShadowing {
+        name: String,
+        location: Location,
+        previous: Location,
+    },
+    /// A type name is used without prior declaration of a type alias.
+    UndeclaredType { name: String, location: Location },
+    /// The definition of a type alias refers to the alias itself.
+    RecursiveTypeAlias { name: String, location: Location },
+    /// A `break` or `continue` statement that is not within the body of a loop of the same
+    /// function.
+    JumpOutsideLoop { keyword: String, location: Location },
+    /// A variable bound by a function statement is the target of an assignment.
+    AssignmentToFunction { name: String, location: Location },
+    /// A variable introduced by a `const` statement is the target of an assignment. `declaration`
+    /// is the location of the `const` statement.
+    AssignToConst {
+        name: String,
+        location: Location,
+        declaration: Location,
+    },
+    /// A record literal or record type has more than one field with the same label.
+    DuplicateField { field: String, location: Location },
     /// Some typing constraints conflict. E.g. a variable must both have type `string` and type
-    /// `number`.
-    ConflictingTypes,
+    /// `number`. `lhs` and `rhs` are the two conflicting types, and `origins` are the locations of
+    /// the expressions whose types are involved in the conflict.
+    ConflictingTypes {
+        location: Location,
+        lhs: String,
+        rhs: String,
+        origins: Vec<Location>,
+    },
+    /// A type must contain itself, for example because a function is applied to itself.
+    InfiniteType {
+        location: Location,
+        ty: String,
+        origins: Vec<Location>,
+    },
+    /// A record type must have a field that it lacks, for example because a field is accessed that
+    /// a record literal does not have. `ty` is the record type without the missing field.
+    MissingField {
+        location: Location,
+        ty: String,
+        field: String,
+        origins: Vec<Location>,
+    },
     /// The type of an expression is not determined, for example because an unused function
     /// argument does not have an explicit type annotation.
-    UndeterminedType,
+    UndeterminedType { location: Location },
+    /// An operator that applies to both numbers and strings is used on operands that are not known
+    /// to be numbers or strings, for example on two function arguments without type annotations.
+    UnresolvedOverload {
+        operator: String,
+        location: Location,
+    },
+    /// The operands of `==` or `!=` have a type whose values can't be compared, e.g. a function
+    /// type. `location` is the location of the comparison or, if the operand type is only known
+    /// to be incomparable where a polymorphic function is used, the location of that usage.
+    IncomparableType { location: Location, ty: String },
+    /// A tuple is indexed by something other than an integer literal that is less than the length
+    /// of the tuple. `ty` is the type of the tuple.
+    InvalidTupleIndex { location: Location, ty: String },
+    /// An array literal in a polymorphic function is used both as an array and as a tuple by
+    /// different uses of the function.
+    ArrayAndTupleLiteral { location: Location },
 }
 
-impl LanguageError {
-    pub fn from_parse_error<T>(
-        err: lalrpop_util::ParseError<usize, T, NeverType>,
+impl Diagnostic {
+    pub fn from_parse_error(
+        err: lalrpop_util::ParseError<usize, Token, NeverType>,
         src: &str,
     ) -> Self {
         use lalrpop_util::ParseError::*;
-        let loc: usize = match err {
-            InvalidToken { location } => location,
-            UnrecognizedEof { location, .. } => location,
+        let (loc, expected, found): (usize, Vec<String>, Option<String>) = match err {
+            InvalidToken { location } => {
+                let found = src[location..].chars().next().map(|c| c.to_string());
+                (location, Vec::new(), found)
+            }
+            UnrecognizedEof { location, expected } => (location, expected, None),
             UnrecognizedToken {
-                token: (location, _, _),
-                ..
-            } => location,
+                token: (location, Token(_, found), _),
+                expected,
+            } => (location, expected, Some(found.to_string())),
             ExtraToken {
-                token: (location, _, _),
-                ..
-            } => location,
+                token: (location, Token(_, found), _),
+            } => (location, Vec::new(), Some(found.to_string())),
             User { error } => match error {},
         };
+        let mut expected: Vec<String> = expected.iter().map(|token| display_token(token)).collect();
+        // `type` is a contextual keyword that is also accepted wherever identifiers are, so we don't
+        // list it separately.
+        if expected.iter().any(|token| token == "identifier") {
+            expected.retain(|token| token != "`type`");
+        }
 
         let consumed_src = &src[..loc];
         let line_index: usize = consumed_src.lines().count().saturating_sub(1);
-        let last_line: Option<&str> = consumed_src.lines().rev().next();
+        let last_line: Option<&str> = consumed_src.lines().next_back();
         let column_index = last_line.map(|l| l.len()).unwrap_or(0);
         Self::ParseError {
-            line: line_index,
-            column: column_index,
+            location: Location {
+                line: line_index,
+                column: column_index,
+            },
+            expected,
+            found,
+        }
+    }
+
+    /// Returns the span of source that a parse error refers to, i.e. the unexpected token or the
+    /// empty span at the end of the file.
+    pub fn parse_error_span(
+        err: &lalrpop_util::ParseError<usize, Token, NeverType>,
+        src: &str,
+    ) -> Span {
+        use lalrpop_util::ParseError::*;
+        match err {
+            InvalidToken { location } => {
+                let len = src[*location..].chars().next().map_or(0, char::len_utf8);
+                Span::new(*location, location + len)
+            }
+            UnrecognizedEof { location, .. } => Span::new(*location, *location),
+            UnrecognizedToken {
+                token: (begin, _, end),
+                ..
+            }
+            | ExtraToken {
+                token: (begin, _, end),
+            } => Span::new(*begin, *end),
+            User { error } => match *error {},
         }
     }
+
+    /// The location at which the error is reported.
+    pub fn location(&self) -> Location {
+        use Diagnostic::*;
+        match self {
+            ParseError { location, .. }
+            | VariableShadowing { location, .. }
+            | UndeclaredVariable { location, .. }
+            | TypeAliasShadowing { location, .. }
+            | UndeclaredType { location, .. }
+            | RecursiveTypeAlias { location, .. }
+            | JumpOutsideLoop { location, .. }
+            | AssignmentToFunction { location, .. }
+            | AssignToConst { location, .. }
+            | DuplicateField { location, .. }
+            | ConflictingTypes { location, .. }
+            | InfiniteType { location, .. }
+            | MissingField { location, .. }
+            | UndeterminedType { location }
+            | UnresolvedOverload { location, .. }
+            | IncomparableType { location, .. }
+            | InvalidTupleIndex { location, .. }
+            | ArrayAndTupleLiteral { location } => *location,
+        }
+    }
+}
+
+/// Renders a token as listed in the `expected` field of lalrpop parse errors, which contains either
+/// the quoted token or the regex of a terminal.
+fn display_token(token: &str) -> String {
+    match token {
+        r##"r#"[A-Za-z][A-Za-z0-9_]*"#"## => "identifier".to_string(),
+        r##"r#"0|[1-9][0-9]*"#"## => "number".to_string(),
+        r##"r#"'[^']*'"#"## => "string".to_string(),
+        _ => match token.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+            Some(token) => format!("`{token}`"),
+            None => token.to_string(),
+        },
+    }
 }
 
-impl fmt::Display for LanguageError {
-    fn fmt<'a>(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        use LanguageError::*;
+/// Writes the locations of expressions involved in a type error if there is more than one.
+fn write_origins(f: &mut fmt::Formatter, origins: &[Location]) -> fmt::Result {
+    if origins.len() > 1 {
+        let origins: Vec<String> = origins.iter().map(|origin| origin.to_string()).collect();
+        write!(f, " (involving expressions at {})", origins.join(", "))?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Diagnostic::*;
         match self {
-            ParseError { line, column } => {
-                let line_number = line + 1;
-                let column_number = column + 1;
-                write!(f, "Syntax error at {line_number}:{column_number}")?;
+            ParseError {
+                location,
+                expected,
+                found,
+            } => {
+                write!(f, "Syntax error at {location}: ")?;
+                let found = match found {
+                    Some(found) => format!("`{found}`"),
+                    None => "end of file".to_string(),
+                };
+                match expected.as_slice() {
+                    [] => write!(f, "unexpected {found}")?,
+                    [expected] => write!(f, "expected {expected} but found {found}")?,
+                    [init @ .., last] => {
+                        let init = init.join(", ");
+                        write!(f, "expected {init} or {last} but found {found}")?;
+                    }
+                }
+            }
+            VariableShadowing {
+                name,
+                location,
+                previous,
+            } => {
+                write!(f, "Variable `{name}` declared more than once at {location}")?;
+                if let Some(previous) = previous {
+                    write!(f, ", previously declared at {previous}")?;
+                }
+            }
+            UndeclaredVariable { name, location } => {
+                write!(f, "Usage of undeclared variable `{name}` at {location}")?;
+            }
+            TypeAliasShadowing {
+                name,
+                location,
+                previous,
+            } => {
+                write!(
+                    f,
+                    "Type `{name}` declared more than once at {location}, previously declared at {previous}"
+                )?;
             }
-            VariableShadowing => {
-                write!(f, "Variable declared more than once")?;
+            UndeclaredType { name, location } => {
+                write!(f, "Usage of undeclared type `{name}` at {location}")?;
             }
-            UndeclaredVariable => {
-                write!(f, "Usage of undeclared variable")?;
+            RecursiveTypeAlias { name, location } => {
+                write!(f, "Type alias `{name}` refers to itself at {location}")?;
             }
-            ConflictingTypes => {
-                write!(f, "Conflicting type constraints")?;
+            JumpOutsideLoop { keyword, location } => {
+                write!(f, "Cannot `{keyword}` outside of a loop at {location}")?;
             }
-            UndeterminedType => {
-                write!(f, "Undetermined type")?;
+            AssignmentToFunction { name, location } => {
+                write!(f, "Cannot assign to function `{name}` at {location}")?;
+            }
+            AssignToConst {
+                name,
+                location,
+                declaration,
+            } => {
+                write!(
+                    f,
+                    "Cannot assign to constant `{name}` at {location}, declared at {declaration}"
+                )?;
+            }
+            DuplicateField { field, location } => {
+                write!(
+                    f,
+                    "Field `{field}` declared more than once in record at {location}"
+                )?;
+            }
+            ConflictingTypes {
+                location,
+                lhs,
+                rhs,
+                origins,
+            } => {
+                write!(
+                    f,
+                    "Conflicting type constraints at {location}: `{lhs}` and `{rhs}`"
+                )?;
+                write_origins(f, origins)?;
+            }
+            InfiniteType {
+                location,
+                ty,
+                origins,
+            } => {
+                write!(f, "Infinite type at {location}: `{ty}`")?;
+                write_origins(f, origins)?;
+            }
+            MissingField {
+                location,
+                ty,
+                field,
+                origins,
+            } => {
+                write!(f, "Record type `{ty}` at {location} has no field `{field}`")?;
+                write_origins(f, origins)?;
+            }
+            UndeterminedType { location } => {
+                write!(f, "Undetermined type at {location}")?;
+            }
+            UnresolvedOverload { operator, location } => {
+                write!(
+                    f,
+                    "Cannot infer whether `{operator}` at {location} applies to numbers or strings"
+                )?;
+            }
+            IncomparableType { location, ty } => {
+                write!(f, "Cannot compare values of type `{ty}` at {location}")?;
+            }
+            InvalidTupleIndex { location, ty } => {
+                write!(
+                    f,
+                    "Tuple of type `{ty}` at {location} must be indexed by an integer literal less than its length"
+                )?;
+            }
+            ArrayAndTupleLiteral { location } => {
+                write!(
+                    f,
+                    "Array literal at {location} can't be used both as an array and as a tuple"
+                )?;
             }
         }
 
@@ -76,4 +368,11 @@ impl fmt::Display for LanguageError {
     }
 }
 
-impl std::error::Error for LanguageError {}
+impl std::error::Error for Diagnostic {}
+
+/// Renders diagnostics one per line.
+#[cfg(test)]
+pub fn display_diagnostics(diagnostics: &[Diagnostic]) -> String {
+    let lines: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+    lines.join("\n")
+}
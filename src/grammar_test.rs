@@ -1,4 +1,5 @@
 use crate::check_source;
+use crate::error::display_diagnostics;
 
 use indoc::indoc;
 
@@ -8,7 +9,10 @@ fn bad_missing_semicolon() {
         let k = 5
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 1:10");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Syntax error at 1:10: expected `!=`, `%`, `&&`, `(`, `*`, `+`, `-`, `.`, `/`, `;`, `<`, `<=`, `==`, `>`, `>=`, `[` or `||` but found end of file"
+    );
 }
 
 #[test]
@@ -17,7 +21,10 @@ fn bad_variable_initialization_missing() {
         let x;
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 1:6");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Syntax error at 1:6: expected `:` or `=` but found `;`"
+    );
 }
 
 #[test]
@@ -26,7 +33,10 @@ fn bad_variable_initialization_missing_expr() {
         let x =;
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 1:8");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Syntax error at 1:8: expected string, number, identifier, `!`, `(`, `-`, `[`, `false`, `function`, `true` or `{` but found `;`"
+    );
 }
 
 #[test]
@@ -35,7 +45,12 @@ fn bad_variable_name_digit() {
         let 53x;
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 1:5");
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Syntax error at 1:5: expected identifier but found `53`
+            Syntax error at 1:7: expected `!=`, `%`, `&&`, `(`, `*`, `+`, `-`, `.`, `/`, `;`, `<`, `<=`, `==`, `>`, `>=`, `[` or `||` but found `x`"}
+    );
 }
 
 #[test]
@@ -44,7 +59,10 @@ fn bad_unterminated_string_literal() {
         let x = 'sdflkjs_djlksdf;
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 1:9");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Syntax error at 1:9: unexpected `'`"
+    );
 }
 
 #[test]
@@ -53,7 +71,10 @@ fn bad_function_args_missing() {
         function asdf {}
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 1:15");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Syntax error at 1:15: expected `(` but found `{`"
+    );
 }
 
 #[test]
@@ -62,7 +83,12 @@ fn bad_function_statement_name_missing() {
         function () {}
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 1:10");
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Syntax error at 1:10: expected identifier but found `(`
+            Syntax error at 1:13: expected `!=`, `%`, `&&`, `(`, `*`, `+`, `-`, `.`, `/`, `;`, `<`, `<=`, `==`, `>`, `>=`, `[` or `||` but found `{`"}
+    );
 }
 
 #[test]
@@ -71,7 +97,10 @@ fn bad_function_body_missing() {
         function asdf ();
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 1:17");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Syntax error at 1:17: expected `:` or `{` but found `;`"
+    );
 }
 
 #[test]
@@ -81,7 +110,10 @@ fn bad_function_unterminated_body() {
           let x =;
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 2:10");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Syntax error at 2:10: expected string, number, identifier, `!`, `(`, `-`, `[`, `false`, `function`, `true` or `{` but found `;`"
+    );
 }
 
 #[test]
@@ -91,7 +123,12 @@ fn bad_function_unterminated_arg_list() {
           let x =;
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 1:26");
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Syntax error at 1:26: expected `)` or `,` but found `{`
+            Syntax error at 2:10: expected string, number, identifier, `!`, `(`, `-`, `[`, `false`, `function`, `true` or `{` but found `;`"}
+    );
 }
 
 #[test]
@@ -104,7 +141,14 @@ fn bad_if_bad_condition_expr() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 2:7");
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Syntax error at 2:8: expected number but found `-`
+            Syntax error at 2:9: expected number but found `-`
+            Syntax error at 2:10: expected number but found `)`
+            Syntax error at 3:5: expected string, number, identifier, `!`, `(`, `-`, `;`, `[`, `break`, `const`, `continue`, `false`, `for`, `function`, `if`, `let`, `return`, `true` or `while` but found `else`"}
+    );
 }
 
 #[test]
@@ -117,7 +161,12 @@ fn bad_if_missing_condition() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 2:6");
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Syntax error at 2:6: expected `(` but found `{`
+            Syntax error at 3:5: expected string, number, identifier, `!`, `(`, `-`, `;`, `[`, `break`, `const`, `continue`, `false`, `for`, `function`, `if`, `let`, `return`, `true` or `while` but found `else`"}
+    );
 }
 
 #[test]
@@ -130,7 +179,10 @@ fn bad_if_missing_true_block() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 3:3");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Syntax error at 3:3: expected `{` but found `else`"
+    );
 }
 
 #[test]
@@ -143,19 +195,38 @@ fn bad_if_unterminated_true_block() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 3:3");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Syntax error at 3:3: expected string, number, identifier, `!`, `(`, `-`, `[`, `break`, `const`, `continue`, `false`, `for`, `function`, `if`, `let`, `return`, `true`, `while` or `}` but found `else`"
+    );
 }
 
 #[test]
-fn bad_if_missing_else() {
-    let err = check_source(&indoc! {"
+fn if_missing_else() {
+    check_source(&indoc! {"
         function asdf (y: (x : void) => boolean) { 
           if (false) {
           }
         }
     "})
-    .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 3:4");
+    .unwrap();
+}
+
+#[test]
+fn else_if_chain() {
+    check_source(&indoc! {"
+        function sign (x: number): number { 
+          if (x < 0) {
+            return -1;
+          } else if (x == 0) {
+            return 0;
+          } else if (x > 0) {
+            return 1;
+          }
+          return 0;
+        }
+    "})
+    .unwrap();
 }
 
 #[test]
@@ -167,7 +238,10 @@ fn bad_if_missing_else_block() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 3:9");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Syntax error at 3:9: expected `if` or `{` but found `}`"
+    );
 }
 
 #[test]
@@ -180,7 +254,10 @@ fn bad_if_unterminated_else_block() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 5:2");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Syntax error at 5:2: expected string, number, identifier, `!`, `(`, `-`, `[`, `break`, `const`, `continue`, `false`, `for`, `function`, `if`, `let`, `return`, `true`, `while` or `}` but found end of file"
+    );
 }
 
 #[test]
@@ -191,7 +268,10 @@ fn bad_while_missing_body() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 2:16");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Syntax error at 2:16: expected `{` but found `}`"
+    );
 }
 
 #[test]
@@ -203,7 +283,10 @@ fn bad_while_unterminated_body() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 4:2");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Syntax error at 4:2: expected string, number, identifier, `!`, `(`, `-`, `[`, `break`, `const`, `continue`, `false`, `for`, `function`, `if`, `let`, `return`, `true`, `while` or `}` but found end of file"
+    );
 }
 
 #[test]
@@ -216,7 +299,12 @@ fn bad_while_missing_condition() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 2:9");
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Syntax error at 2:9: expected `(` but found `{`
+            Syntax error at 4:4: expected string, number, identifier, `!`, `(`, `-`, `[`, `break`, `const`, `continue`, `false`, `for`, `function`, `if`, `let`, `return`, `true` or `while` but found `}`"}
+    );
 }
 
 #[test]
@@ -229,7 +317,12 @@ fn bad_while_missing_condition_expr() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 2:10");
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Syntax error at 2:10: expected string, number, identifier, `!`, `(`, `-`, `[`, `false`, `function`, `true` or `{` but found `)`
+            Syntax error at 4:4: expected string, number, identifier, `!`, `(`, `-`, `[`, `break`, `const`, `continue`, `false`, `for`, `function`, `if`, `let`, `return`, `true` or `while` but found `}`"}
+    );
 }
 
 #[test]
@@ -242,7 +335,14 @@ fn bad_while_bad_condition_expr() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 2:10");
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Syntax error at 2:11: expected number but found `-`
+            Syntax error at 2:12: expected number but found `-`
+            Syntax error at 2:13: expected number but found `)`
+            Syntax error at 4:4: expected string, number, identifier, `!`, `(`, `-`, `[`, `break`, `const`, `continue`, `false`, `for`, `function`, `if`, `let`, `return`, `true` or `while` but found `}`"}
+    );
 }
 
 #[test]
@@ -253,7 +353,10 @@ fn bad_expr_return() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 2:11");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Syntax error at 2:11: expected string, number, identifier, `!`, `(`, `-`, `[`, `false`, `function`, `true` or `{` but found `return`"
+    );
 }
 
 #[test]
@@ -264,7 +367,10 @@ fn bad_return_expr_type() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 2:10");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Syntax error at 2:10: expected string, number, identifier, `!`, `(`, `-`, `;`, `[`, `false`, `function`, `true` or `{` but found `number`"
+    );
 }
 
 #[test]
@@ -275,7 +381,10 @@ fn bad_app_expr_unterminated_args() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 2:8");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Syntax error at 2:8: expected string, number, identifier, `!`, `(`, `)`, `-`, `[`, `false`, `function`, `true` or `{` but found `;`"
+    );
 }
 
 #[test]
@@ -287,7 +396,12 @@ fn bad_app_expr_missing_arg_comma() {
         asdf(x x);
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 4:8");
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Syntax error at 4:8: expected `!=`, `%`, `&&`, `(`, `)`, `*`, `+`, `,`, `-`, `.`, `/`, `<`, `<=`, `==`, `>`, `>=`, `[` or `||` but found `x`
+            Syntax error at 4:9: expected `!=`, `%`, `&&`, `(`, `*`, `+`, `-`, `.`, `/`, `;`, `<`, `<=`, `=`, `==`, `>`, `>=`, `[` or `||` but found `)`"}
+    );
 }
 
 #[test]
@@ -298,7 +412,12 @@ fn bad_equals_missing_lhs() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 1:5");
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Syntax error at 1:5: expected string, number, identifier, `!`, `(`, `-`, `[`, `false`, `function`, `true` or `{` but found `==`
+            Syntax error at 1:9: expected `;` but found `)`"}
+    );
 }
 
 #[test]
@@ -309,7 +428,10 @@ fn bad_equals_missing_rhs() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 1:10");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Syntax error at 1:10: expected string, number, identifier, `!`, `(`, `-`, `[`, `false`, `true` or `{` but found `)`"
+    );
 }
 
 #[test]
@@ -320,5 +442,160 @@ fn bad_equals_doesnt_associate() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Syntax error at 1:12");
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Syntax error at 1:12: expected `&&`, `)` or `||` but found `==`
+            Syntax error at 1:16: expected `;` but found `)`"}
+    );
+}
+
+#[test]
+fn bad_comparison_doesnt_associate() {
+    let err = check_source(&indoc! {"
+        let a = 1 < 2 < 3;
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Syntax error at 1:15: expected `!=`, `&&`, `+`, `-`, `;`, `==` or `||` but found `<`"
+    );
+}
+
+#[test]
+fn bad_not_missing_operand() {
+    let err = check_source(&indoc! {"
+        let a = true && !;
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Syntax error at 1:18: expected string, number, identifier, `!`, `(`, `-`, `[`, `false`, `true` or `{` but found `;`"
+    );
+}
+
+#[test]
+fn bad_syntax_errors_recovered() {
+    let err = check_source(&indoc! {"
+        let x = ;
+        let y: number = 'abc';
+        function foo() {
+            let z = 5
+        }
+        if (true) else {}
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Syntax error at 1:9: expected string, number, identifier, `!`, `(`, `-`, `[`, `false`, `function`, `true` or `{` but found `;`
+            Conflicting type constraints at 2:17: `number` and `string`
+            Syntax error at 4:14: expected `;` but found `}`
+            Syntax error at 6:11: expected `{` but found `else`"}
+    );
+}
+
+#[test]
+fn for_empty_header_parts() {
+    check_source(&indoc! {"
+        let i = 0;
+        for (; i < 3;) {
+            i = i + 1;
+        }
+        for (i = 0; i < 3; i) {}
+    "})
+    .unwrap();
+}
+
+#[test]
+fn bad_for_let_in_update() {
+    let err = check_source(&indoc! {"
+        for (let i = 0; i < 3; let j = i) {}
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Syntax error at 1:24: expected string, number, identifier, `!`, `(`, `)`, `-`, `[`, `false`, `function` or `true` but found `let`
+            Syntax error at 1:33: expected `;` but found `)`"}
+    );
+}
+
+#[test]
+fn array_type_annotations() {
+    check_source(&indoc! {"
+        let xs: number[][] = [[1]];
+        let fs: ((x: number) => number[])[] = [];
+        function f(g: (x: number) => number[]): number {
+            return g(xs[0][0])[0];
+        }
+        fs = [function id(x: number) { return [x]; }];
+        f(fs[0]);
+    "})
+    .unwrap();
+}
+
+#[test]
+fn tuple_type_annotations() {
+    check_source(&indoc! {"
+        let t: [number, [string, boolean[]]][] = [];
+        let u: [] = [];
+        let f: [(x: number) => number, number] = [function id(x: number) { return x; }, 1];
+        t = [[f[0](f[1]), ['a', [true]]]];
+    "})
+    .unwrap();
+}
+
+#[test]
+fn record_type_annotations() {
+    check_source(&indoc! {"
+        let empty: {} = {};
+        let point: { x: number; y: number } = { x: 1, y: 2 };
+        let nested: { origin: { x: number; y: number }; f: (x: number) => { y: number } }[] = [];
+        nested = [{ origin: point, f: function f(x: number) { return { y: x }; } }];
+        let y = nested[0].f(nested[0].origin.x).y;
+    "})
+    .unwrap();
+}
+
+#[test]
+fn bad_record_literal_statement() {
+    let err = check_source(&indoc! {"
+        { a: 1 };
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Syntax error at 1:1: expected string, number, identifier, `!`, `(`, `-`, `[`, `break`, `const`, `continue`, `false`, `for`, `function`, `if`, `let`, `return`, `true` or `while` but found `{`
+            Syntax error at 1:4: expected `!=`, `%`, `&&`, `(`, `*`, `+`, `-`, `.`, `/`, `;`, `<`, `<=`, `=`, `==`, `>`, `>=`, `[` or `||` but found `:`
+            Syntax error at 1:8: expected `!=`, `%`, `&&`, `(`, `*`, `+`, `-`, `.`, `/`, `;`, `<`, `<=`, `==`, `>`, `>=`, `[` or `||` but found `}`"}
+    );
+}
+
+#[test]
+fn type_alias_annotations() {
+    check_source(&indoc! {"
+        type Point = { x: number; y: number };
+        type Endo = (x: number) => number;
+        let points: Point[] = [];
+        let fs: (Endo[])[] = [];
+        let g: (f: Endo) => Endo = function g(f: Endo) { return f; };
+    "})
+    .unwrap();
+}
+
+#[test]
+fn type_contextual_keyword() {
+    check_source(&indoc! {"
+        let type = 5;
+        type = type + 1;
+        type Kind = { type: number };
+        let kind: Kind = { type: type };
+        function f(k: Kind) {
+            return k.type;
+        }
+        type = f(kind);
+    "})
+    .unwrap();
 }
@@ -1,11 +1,16 @@
 use crate::program::*;
 
 use itertools::Itertools;
+use lalrpop_util::lexer::Token;
+use lalrpop_util::ErrorRecovery;
 use std::collections::HashMap;
 
 // TODO: Use rust's built-in never type ! once it is stabilized.
 pub enum NeverType {}
 
+/// The syntax errors the parser has recovered from.
+pub type SyntaxErrors<'input> = Vec<ErrorRecovery<usize, Token<'input>, NeverType>>;
+
 /// Erases all line comments (i.e., those starting with `//`).
 ///
 /// Since we're only deleting suffixes of lines, positions given by a (line, column) pair into the
@@ -27,6 +32,9 @@ pub struct Literals {
     pub vars: HashMap<String, Var>,
     pub strings: HashMap<String, StringLiteral>,
     pub numbers: HashMap<String, NumberLiteral>,
+    pub fields: HashMap<String, Field>,
+    /// The names of type aliases, which live in a namespace separate from that of variables.
+    pub type_names: HashMap<String, TypeName>,
 }
 
 impl Literals {
@@ -35,10 +43,146 @@ impl Literals {
             vars: HashMap::new(),
             strings: HashMap::new(),
             numbers: HashMap::new(),
+            fields: HashMap::new(),
+            type_names: HashMap::new(),
+        }
+    }
+
+    /// Returns the source name of a variable.
+    pub fn var_name(&self, var: Var) -> &str {
+        self.vars
+            .iter()
+            .find(|(_, v)| **v == var)
+            .map(|(name, _)| name.as_str())
+            .expect("variable should be in literals")
+    }
+
+    /// Returns the source name of a field label.
+    pub fn field_name(&self, field: Field) -> &str {
+        self.fields
+            .iter()
+            .find(|(_, f)| **f == field)
+            .map(|(name, _)| name.as_str())
+            .expect("field should be in literals")
+    }
+
+    /// Returns the source name of a type name.
+    pub fn type_name_str(&self, name: TypeName) -> &str {
+        self.type_names
+            .iter()
+            .find(|(_, n)| **n == name)
+            .map(|(name, _)| name.as_str())
+            .expect("type name should be in literals")
+    }
+}
+
+/// A range of byte offsets `[begin, end)` into the source with comments erased.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Span {
+    pub begin: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(begin: usize, end: usize) -> Span {
+        Span { begin, end }
+    }
+}
+
+/// The source spans of AST nodes, as recorded by the parser.
+///
+/// AST node elements are never equated with each other when the program model is closed, so the
+/// node elements created during parsing remain valid keys afterwards.
+#[derive(Clone, Debug)]
+pub struct Spans {
+    pub exprs: HashMap<ExprNode, Span>,
+    pub stmts: HashMap<StmtNode, Span>,
+    pub functions: HashMap<FunctionNode, Span>,
+    /// The span of the name of each function.
+    pub function_names: HashMap<FunctionNode, Span>,
+    pub types: HashMap<TypeNode, Span>,
+    /// The span of each optional `: type` annotation. For omitted annotations, this is the empty
+    /// span at the position where the annotation could be inserted.
+    pub opt_types: HashMap<OptTypeNode, Span>,
+    /// The span of the head argument `var: type` for each cons arg list node.
+    pub args: HashMap<ArgListNode, Span>,
+    /// The span from the head statement to the end of the enclosing block for each cons statement
+    /// list node.
+    pub stmt_lists: HashMap<StmtListNode, Span>,
+    /// The spans of source that the parser skipped to recover from syntax errors. Nodes created
+    /// within these spans may have been discarded and are not part of the module.
+    pub syntax_errors: Vec<Span>,
+}
+
+impl Spans {
+    pub fn new() -> Spans {
+        Spans {
+            exprs: HashMap::new(),
+            stmts: HashMap::new(),
+            functions: HashMap::new(),
+            function_names: HashMap::new(),
+            types: HashMap::new(),
+            opt_types: HashMap::new(),
+            args: HashMap::new(),
+            stmt_lists: HashMap::new(),
+            syntax_errors: Vec::new(),
+        }
+    }
+}
+
+/// A `Program::insert_<operator>_expr_node` function for a binary operator.
+pub type InsertBinaryExprNode = fn(&mut Program, ExprNode, ExprNode, ExprNode);
+
+/// Returns the number literal element for the source text `s` of a number literal.
+pub fn number_literal(s: &str, p: &mut Program, literals: &mut Literals) -> NumberLiteral {
+    match literals.numbers.get(s) {
+        Some(num) => *num,
+        None => {
+            let num = p.new_number_literal();
+            literals.numbers.insert(s.to_string(), num);
+            num
+        }
+    }
+}
+
+/// Returns the variable element for the name `s`.
+pub fn var(s: &str, p: &mut Program, literals: &mut Literals) -> Var {
+    match literals.vars.get(s) {
+        Some(v) => *v,
+        None => {
+            let v = p.new_var();
+            literals.vars.insert(s.to_string(), v);
+            v
         }
     }
 }
 
+/// Returns the field label element for the name `s`. New labels are recorded as distinct from all
+/// labels seen before.
+pub fn field(s: &str, p: &mut Program, literals: &mut Literals) -> Field {
+    if let Some(field) = literals.fields.get(s) {
+        return *field;
+    }
+    let field = p.new_field();
+    for other in literals.fields.values() {
+        p.insert_distinct_fields(field, *other);
+        p.insert_distinct_fields(*other, field);
+    }
+    literals.fields.insert(s.to_string(), field);
+    field
+}
+
+pub fn field_list_node(fields: &[Field], p: &mut Program) -> FieldListNode {
+    let mut l = p.new_field_list_node();
+    p.insert_nil_field_list_node(l);
+    for field in fields.iter().rev() {
+        let cons = p.new_field_list_node();
+        p.insert_cons_field_list_node(cons, *field, l);
+        l = cons;
+    }
+    l
+}
+
 pub fn expr_list_node(nodes: &[ExprNode], p: &mut Program) -> ExprListNode {
     let mut l = p.new_expr_list_node();
     p.insert_nil_expr_list_node(l);
@@ -50,19 +194,47 @@ pub fn expr_list_node(nodes: &[ExprNode], p: &mut Program) -> ExprListNode {
     l
 }
 
-pub fn stmt_list_node(nodes: &[StmtNode], p: &mut Program) -> StmtListNode {
+pub fn type_list_node(nodes: &[TypeNode], p: &mut Program) -> TypeListNode {
+    let mut l = p.new_type_list_node();
+    p.insert_nil_type_list_node(l);
+    for node in nodes.iter().rev() {
+        let cons = p.new_type_list_node();
+        p.insert_cons_type_list_node(cons, *node, l);
+        l = cons;
+    }
+    l
+}
+
+/// Creates a statement list node for the statements of a block which ends at byte offset `end`.
+pub fn stmt_list_node(
+    nodes: &[StmtNode],
+    end: usize,
+    p: &mut Program,
+    spans: &mut Spans,
+) -> StmtListNode {
     let mut l = p.new_stmt_list_node();
     p.insert_nil_stmt_list_node(l);
     for node in nodes.iter().rev() {
         let cons = p.new_stmt_list_node();
         p.insert_cons_stmt_list_node(cons, *node, l);
+        spans
+            .stmt_lists
+            .insert(cons, Span::new(spans.stmts[node].begin, end));
         l = cons;
     }
     l
 }
 
-pub fn opt_type_node(node: Option<TypeNode>, p: &mut Program) -> OptTypeNode {
+/// Creates an optional type annotation node. `span` is the span of the annotation including the
+/// colon, or the empty span at the position where the annotation could be if it is omitted.
+pub fn opt_type_node(
+    node: Option<TypeNode>,
+    span: Span,
+    p: &mut Program,
+    spans: &mut Spans,
+) -> OptTypeNode {
     let otn = p.new_opt_type_node();
+    spans.opt_types.insert(otn, span);
     match node {
         Some(node) => {
             p.insert_some_opt_type_node(otn, node);
@@ -74,12 +246,17 @@ pub fn opt_type_node(node: Option<TypeNode>, p: &mut Program) -> OptTypeNode {
     otn
 }
 
-pub fn arg_list_node(args: &[(Var, OptTypeNode)], p: &mut Program) -> ArgListNode {
+pub fn arg_list_node(
+    args: &[(Var, OptTypeNode, Span)],
+    p: &mut Program,
+    spans: &mut Spans,
+) -> ArgListNode {
     let mut l = p.new_arg_list_node();
     p.insert_nil_arg_list_node(l);
-    for (var, otn) in args.iter().rev() {
+    for (var, otn, span) in args.iter().rev() {
         let cons = p.new_arg_list_node();
         p.insert_cons_arg_list_node(cons, *var, *otn, l);
+        spans.args.insert(cons, *span);
         l = cons;
     }
     l
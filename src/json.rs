@@ -0,0 +1,308 @@
+use std::fmt;
+
+/// A JSON value, as exchanged with language server clients.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// The members of an object in source order.
+    Object(Vec<(String, Json)>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JsonError {
+    /// The byte offset in the input at which parsing failed.
+    pub offset: usize,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid JSON at byte {}", self.offset)
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+impl Json {
+    /// Builds an object from its members.
+    pub fn object<const N: usize>(members: [(&str, Json); N]) -> Json {
+        let members = members
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect();
+        Json::Object(members)
+    }
+
+    /// Returns the value of an object member, or `None` if `self` is not an object or has no such
+    /// member.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(key0, _)| key0 == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as usize),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn parse(src: &str) -> Result<Json, JsonError> {
+        let mut parser = Parser { src, offset: 0 };
+        let value = parser.value()?;
+        parser.whitespace();
+        if parser.offset != src.len() {
+            return Err(parser.error());
+        }
+        Ok(value)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Json {
+        Json::String(s.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Json {
+        Json::String(s)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Json {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Json {
+        Json::Bool(b)
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{b}"),
+            Json::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Json::Number(n) => write!(f, "{n}"),
+            Json::String(s) => write_string(f, s),
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    offset: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self) -> JsonError {
+        JsonError {
+            offset: self.offset,
+        }
+    }
+
+    fn rest(&self) -> &str {
+        &self.src[self.offset..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        Some(c)
+    }
+
+    fn whitespace(&mut self) {
+        while let Some(' ' | '\t' | '\n' | '\r') = self.peek() {
+            self.offset += 1;
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), JsonError> {
+        match self.rest().strip_prefix(token) {
+            Some(_) => {
+                self.offset += token.len();
+                Ok(())
+            }
+            None => Err(self.error()),
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, JsonError> {
+        self.whitespace();
+        match self.peek() {
+            Some('n') => self.expect("null").map(|_| Json::Null),
+            Some('t') => self.expect("true").map(|_| Json::Bool(true)),
+            Some('f') => self.expect("false").map(|_| Json::Bool(false)),
+            Some('"') => self.string().map(Json::String),
+            Some('[') => self.array(),
+            Some('{') => self.object(),
+            Some('-' | '0'..='9') => self.number(),
+            _ => Err(self.error()),
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, JsonError> {
+        let len = self
+            .rest()
+            .find(|c: char| !matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9'))
+            .unwrap_or(self.rest().len());
+        let n: f64 = self.rest()[..len].parse().map_err(|_| self.error())?;
+        self.offset += len;
+        Ok(Json::Number(n))
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self.rest().get(..4).ok_or(self.error())?;
+        let code = u32::from_str_radix(digits, 16).map_err(|_| self.error())?;
+        self.offset += 4;
+        Ok(code)
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect("\"")?;
+        let mut result = String::new();
+        loop {
+            match self.next().ok_or(self.error())? {
+                '"' => return Ok(result),
+                '\\' => {
+                    let c = match self.next().ok_or(self.error())? {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => {
+                            let mut code = self.hex4()?;
+                            // Characters outside the basic multilingual plane are encoded as
+                            // UTF-16 surrogate pairs.
+                            if (0xD800..0xDC00).contains(&code) {
+                                self.expect("\\u")?;
+                                let low = self.hex4()?;
+                                code =
+                                    0x10000 + ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00));
+                            }
+                            char::from_u32(code).ok_or(self.error())?
+                        }
+                        _ => return Err(self.error()),
+                    };
+                    result.push(c);
+                }
+                c => result.push(c),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, JsonError> {
+        self.expect("[")?;
+        let mut values = Vec::new();
+        self.whitespace();
+        if self.peek() == Some(']') {
+            self.offset += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.whitespace();
+            match self.next() {
+                Some(',') => {}
+                Some(']') => return Ok(Json::Array(values)),
+                _ => return Err(self.error()),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, JsonError> {
+        self.expect("{")?;
+        let mut members = Vec::new();
+        self.whitespace();
+        if self.peek() == Some('}') {
+            self.offset += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.whitespace();
+            let key = self.string()?;
+            self.whitespace();
+            self.expect(":")?;
+            let value = self.value()?;
+            members.push((key, value));
+            self.whitespace();
+            match self.next() {
+                Some(',') => {}
+                Some('}') => return Ok(Json::Object(members)),
+                _ => return Err(self.error()),
+            }
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use crate::json::Json;
+
+#[test]
+fn parse_values() {
+    let json = Json::parse(r#" {"a": [1, -2.5, true, null], "b": {"c": "d"}} "#).unwrap();
+    assert_eq!(
+        json,
+        Json::object([
+            (
+                "a",
+                Json::Array(vec![
+                    Json::Number(1.0),
+                    Json::Number(-2.5),
+                    Json::Bool(true),
+                    Json::Null,
+                ])
+            ),
+            ("b", Json::object([("c", "d".into())])),
+        ])
+    );
+}
+
+#[test]
+fn parse_string_escapes() {
+    let json = Json::parse(r#""a\"b\\c\ndé😀""#).unwrap();
+    assert_eq!(json, Json::String("a\"b\\c\nd\u{e9}\u{1f600}".to_string()));
+}
+
+#[test]
+fn print_values() {
+    let json = Json::object([
+        ("id", 3.into()),
+        ("text", "let x = 'a\"b';\n".into()),
+        ("items", Json::Array(vec![Json::Null, false.into()])),
+    ]);
+    assert_eq!(
+        json.to_string(),
+        r#"{"id":3,"text":"let x = 'a\"b';\n","items":[null,false]}"#
+    );
+    assert_eq!(Json::parse(&json.to_string()).unwrap(), json);
+}
+
+#[test]
+fn bad_trailing_input() {
+    assert!(Json::parse("[1, 2] 3").is_err());
+    assert!(Json::parse(r#"{"a" 1}"#).is_err());
+    assert!(Json::parse(r#""unterminated"#).is_err());
+}
@@ -0,0 +1,320 @@
+use crate::declarations::{declarations, resolve};
+use crate::error::{Diagnostic, Location};
+use crate::grammar_util::{erase_comments, Span};
+use crate::json::Json;
+use crate::program::*;
+use crate::type_printer::TypePrinter;
+use crate::{check_module, CheckedModule};
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{self, BufRead, Write};
+use std::panic::{self, AssertUnwindSafe};
+
+fn invalid_data(err: impl Into<Box<dyn Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// Reads a JSON-RPC message preceded by a `Content-Length` header. Returns `None` at the end of
+/// the input.
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Json>> {
+    let mut content_length: Option<usize> = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(len) = line.strip_prefix("Content-Length:") {
+            content_length = Some(len.trim().parse().map_err(invalid_data)?);
+        }
+    }
+
+    let content_length = content_length.ok_or_else(|| invalid_data("missing Content-Length"))?;
+    let mut content = vec![0; content_length];
+    reader.read_exact(&mut content)?;
+    let content = String::from_utf8(content).map_err(invalid_data)?;
+    Json::parse(&content).map(Some).map_err(invalid_data)
+}
+
+/// Writes a JSON-RPC message preceded by a `Content-Length` header.
+pub fn write_message(writer: &mut impl Write, message: &Json) -> io::Result<()> {
+    let content = message.to_string();
+    write!(
+        writer,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    writer.flush()
+}
+
+/// Converts a line and a byte offset into that line to an LSP position, whose character offsets
+/// count UTF-16 code units.
+fn position(src: &str, location: Location) -> Json {
+    let line = src.split('\n').nth(location.line).unwrap_or("");
+    let column = location.column.min(line.len());
+    let character = line[..column].encode_utf16().count();
+    Json::object([
+        ("line", location.line.into()),
+        ("character", character.into()),
+    ])
+}
+
+/// Converts an LSP position to a byte offset into `src`. Positions past the end of a line are
+/// clamped to the end of the line.
+fn offset(src: &str, position: &Json) -> Option<usize> {
+    let line = position.get("line")?.as_usize()?;
+    let character = position.get("character")?.as_usize()?;
+    let line_begin = match line {
+        0 => 0,
+        _ => src.match_indices('\n').nth(line - 1)?.0 + 1,
+    };
+
+    let mut units = 0;
+    for (i, c) in src[line_begin..].char_indices() {
+        if units >= character || c == '\n' {
+            return Some(line_begin + i);
+        }
+        units += c.len_utf16();
+    }
+    Some(src.len())
+}
+
+/// An open text document and the result of checking it.
+struct Document {
+    /// The source with comments erased, into which the spans of the checked module point.
+    no_comments_src: String,
+    checked: Option<CheckedModule>,
+    diagnostics: Vec<(Diagnostic, Span)>,
+}
+
+impl Document {
+    fn new(src: &str) -> Document {
+        // A bug in the checker shouldn't bring down the server, so we treat a document on which the
+        // checker panics as one without a module or diagnostics.
+        let (checked, diagnostics) =
+            panic::catch_unwind(|| check_module(src)).unwrap_or((None, Vec::new()));
+        Document {
+            no_comments_src: erase_comments(src),
+            checked,
+            diagnostics,
+        }
+    }
+
+    fn range(&self, span: Span) -> Json {
+        let src = &self.no_comments_src;
+        Json::object([
+            (
+                "start",
+                position(src, Location::from_offset(src, span.begin)),
+            ),
+            ("end", position(src, Location::from_offset(src, span.end))),
+        ])
+    }
+
+    /// Returns the innermost expression whose span contains `offset`.
+    fn expr_at(&self, offset: usize) -> Option<ExprNode> {
+        let (p, _, spans, _) = self.checked.as_ref()?;
+        p.iter_expr_node()
+            .filter_map(|expr| Some((expr, *spans.exprs.get(&expr)?)))
+            .filter(|(_, span)| span.begin <= offset && offset < span.end)
+            .min_by_key(|(_, span)| span.end - span.begin)
+            .map(|(expr, _)| expr)
+    }
+
+    fn hover(&self, offset: usize) -> Json {
+        let (Some(expr), Some((p, lits, spans, _))) = (self.expr_at(offset), self.checked.as_ref())
+        else {
+            return Json::Null;
+        };
+        let (Some(sigma), Some(span)) = (p.expr_type(expr), spans.exprs.get(&expr)) else {
+            return Json::Null;
+        };
+        let ty = TypePrinter::new(p, lits).print(sigma);
+        Json::object([
+            (
+                "contents",
+                Json::object([("kind", "plaintext".into()), ("value", ty.into())]),
+            ),
+            ("range", self.range(*span)),
+        ])
+    }
+
+    fn definition(&self, uri: &str, offset: usize) -> Json {
+        let (Some(expr), Some((p, _, spans, _))) = (self.expr_at(offset), self.checked.as_ref())
+        else {
+            return Json::Null;
+        };
+        let (Some((_, var)), Some(span)) = (
+            p.iter_variable_expr_node()
+                .find(|(expr0, _)| *expr0 == expr),
+            spans.exprs.get(&expr),
+        ) else {
+            return Json::Null;
+        };
+        let decls = declarations(p, spans);
+        match resolve(&decls, var, span.begin) {
+            Some(decl) => Json::object([("uri", uri.into()), ("range", self.range(decl.span))]),
+            None => Json::Null,
+        }
+    }
+
+    fn lsp_diagnostics(&self) -> Json {
+        let diagnostics = self
+            .diagnostics
+            .iter()
+            .map(|(diagnostic, span)| {
+                Json::object([
+                    ("range", self.range(*span)),
+                    // Error.
+                    ("severity", 1.into()),
+                    ("source", "ts".into()),
+                    ("message", diagnostic.to_string().into()),
+                ])
+            })
+            .collect();
+        Json::Array(diagnostics)
+    }
+}
+
+/// A language server for a single client.
+struct Server<W: Write> {
+    writer: W,
+    documents: HashMap<String, Document>,
+}
+
+impl<W: Write> Server<W> {
+    fn respond(&mut self, id: &Json, result: Json) -> io::Result<()> {
+        let response = Json::object([
+            ("jsonrpc", "2.0".into()),
+            ("id", id.clone()),
+            ("result", result),
+        ]);
+        write_message(&mut self.writer, &response)
+    }
+
+    fn respond_error(&mut self, id: &Json, code: f64, message: &str) -> io::Result<()> {
+        let error = Json::object([("code", Json::Number(code)), ("message", message.into())]);
+        let response = Json::object([
+            ("jsonrpc", "2.0".into()),
+            ("id", id.clone()),
+            ("error", error),
+        ]);
+        write_message(&mut self.writer, &response)
+    }
+
+    fn publish_diagnostics(&mut self, uri: &str) -> io::Result<()> {
+        let diagnostics = match self.documents.get(uri) {
+            Some(document) => document.lsp_diagnostics(),
+            None => Json::Array(Vec::new()),
+        };
+        let notification = Json::object([
+            ("jsonrpc", "2.0".into()),
+            ("method", "textDocument/publishDiagnostics".into()),
+            (
+                "params",
+                Json::object([("uri", uri.into()), ("diagnostics", diagnostics)]),
+            ),
+        ]);
+        write_message(&mut self.writer, &notification)
+    }
+
+    /// Handles a request or a notification. Returns false if the server should exit.
+    fn handle(&mut self, message: &Json) -> io::Result<bool> {
+        let method = message.get("method").and_then(Json::as_str).unwrap_or("");
+        let params = message.get("params").unwrap_or(&Json::Null);
+        let uri = params
+            .get("textDocument")
+            .and_then(|doc| doc.get("uri"))
+            .and_then(Json::as_str)
+            .unwrap_or("")
+            .to_string();
+
+        match method {
+            "exit" => return Ok(false),
+            "textDocument/didOpen" => {
+                let src = params
+                    .get("textDocument")
+                    .and_then(|doc| doc.get("text"))
+                    .and_then(Json::as_str)
+                    .unwrap_or("");
+                self.documents.insert(uri.clone(), Document::new(src));
+                self.publish_diagnostics(&uri)?;
+            }
+            "textDocument/didChange" => {
+                // We only support full document synchronization, so the last change contains the
+                // full text.
+                let src = params
+                    .get("contentChanges")
+                    .and_then(Json::as_array)
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text"))
+                    .and_then(Json::as_str)
+                    .unwrap_or("");
+                self.documents.insert(uri.clone(), Document::new(src));
+                self.publish_diagnostics(&uri)?;
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                self.publish_diagnostics(&uri)?;
+            }
+            _ => {}
+        }
+
+        // Only requests have an id and must be answered.
+        let Some(id) = message.get("id") else {
+            return Ok(true);
+        };
+        let document = self.documents.get(&uri);
+        let offset = document
+            .and_then(|document| offset(&document.no_comments_src, params.get("position")?));
+        let result = match (method, document, offset) {
+            ("initialize", _, _) => {
+                let capabilities = Json::object([
+                    // Full document synchronization.
+                    ("textDocumentSync", 1.into()),
+                    ("hoverProvider", true.into()),
+                    ("definitionProvider", true.into()),
+                ]);
+                Json::object([("capabilities", capabilities)])
+            }
+            ("shutdown", _, _) => Json::Null,
+            // Like checking, answering requests shouldn't bring down the server if it panics.
+            ("textDocument/hover", Some(document), Some(offset)) => {
+                panic::catch_unwind(AssertUnwindSafe(|| document.hover(offset)))
+                    .unwrap_or(Json::Null)
+            }
+            ("textDocument/definition", Some(document), Some(offset)) => {
+                panic::catch_unwind(AssertUnwindSafe(|| document.definition(&uri, offset)))
+                    .unwrap_or(Json::Null)
+            }
+            ("textDocument/hover" | "textDocument/definition", _, _) => Json::Null,
+            _ => {
+                // Method not found.
+                self.respond_error(id, -32601.0, &format!("Unsupported method {method}"))?;
+                return Ok(true);
+            }
+        };
+        self.respond(id, result)?;
+        Ok(true)
+    }
+}
+
+/// Runs a language server which communicates via JSON-RPC messages on `reader` and `writer` until
+/// the client sends the `exit` notification or closes the input.
+pub fn run(mut reader: impl BufRead, writer: impl Write) -> io::Result<()> {
+    let mut server = Server {
+        writer,
+        documents: HashMap::new(),
+    };
+    while let Some(message) = read_message(&mut reader)? {
+        if !server.handle(&message)? {
+            break;
+        }
+    }
+    Ok(())
+}
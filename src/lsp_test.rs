@@ -0,0 +1,161 @@
+use crate::json::Json;
+use crate::lsp::{read_message, run, write_message};
+
+/// Runs the language server on messages sent by a scripted client and returns the messages that
+/// the server sends back.
+fn session(messages: &[&str]) -> Vec<Json> {
+    let mut input: Vec<u8> = Vec::new();
+    for message in messages {
+        write_message(&mut input, &Json::parse(message).unwrap()).unwrap();
+    }
+    let mut output: Vec<u8> = Vec::new();
+    run(input.as_slice(), &mut output).unwrap();
+
+    let mut output = output.as_slice();
+    let mut responses = Vec::new();
+    while let Some(response) = read_message(&mut output).unwrap() {
+        responses.push(response);
+    }
+    responses
+}
+
+fn did_open(text: &str) -> String {
+    let text = Json::from(text);
+    format!(
+        r#"{{"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {{"textDocument": {{"uri": "file:///a.ts", "languageId": "typescript", "version": 1, "text": {text}}}}}}}"#
+    )
+}
+
+fn position_request(id: usize, method: &str, line: usize, character: usize) -> String {
+    format!(
+        r#"{{"jsonrpc": "2.0", "id": {id}, "method": "{method}", "params": {{"textDocument": {{"uri": "file:///a.ts"}}, "position": {{"line": {line}, "character": {character}}}}}}}"#
+    )
+}
+
+fn range(start: (usize, usize), end: (usize, usize)) -> Json {
+    let position = |(line, character): (usize, usize)| {
+        Json::object([("line", line.into()), ("character", character.into())])
+    };
+    Json::object([("start", position(start)), ("end", position(end))])
+}
+
+#[test]
+fn initialize_and_shutdown() {
+    let responses = session(&[
+        r#"{"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {"capabilities": {}}}"#,
+        r#"{"jsonrpc": "2.0", "method": "initialized", "params": {}}"#,
+        r#"{"jsonrpc": "2.0", "id": 2, "method": "shutdown"}"#,
+        r#"{"jsonrpc": "2.0", "method": "exit"}"#,
+        r#"{"jsonrpc": "2.0", "id": 3, "method": "shutdown"}"#,
+    ]);
+    assert_eq!(responses.len(), 2);
+
+    let capabilities = responses[0]
+        .get("result")
+        .and_then(|result| result.get("capabilities"))
+        .unwrap();
+    assert_eq!(capabilities.get("hoverProvider"), Some(&Json::Bool(true)));
+    assert_eq!(
+        capabilities.get("definitionProvider"),
+        Some(&Json::Bool(true))
+    );
+
+    assert_eq!(responses[1].get("id"), Some(&Json::Number(2.0)));
+    assert_eq!(responses[1].get("result"), Some(&Json::Null));
+}
+
+#[test]
+fn publish_diagnostics_on_change() {
+    let responses = session(&[
+        &did_open("let x: number = 'abc';\n"),
+        r#"{"jsonrpc": "2.0", "method": "textDocument/didChange", "params": {"textDocument": {"uri": "file:///a.ts", "version": 2}, "contentChanges": [{"text": "let x: number = 5;\n"}]}}"#,
+    ]);
+    assert_eq!(responses.len(), 2);
+
+    let params = responses[0].get("params").unwrap();
+    assert_eq!(
+        responses[0].get("method").and_then(Json::as_str),
+        Some("textDocument/publishDiagnostics")
+    );
+    assert_eq!(
+        params.get("uri").and_then(Json::as_str),
+        Some("file:///a.ts")
+    );
+    let diagnostics = params.get("diagnostics").and_then(Json::as_array).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].get("message").and_then(Json::as_str),
+        Some("Conflicting type constraints at 1:17: `number` and `string`")
+    );
+    assert_eq!(diagnostics[0].get("range"), Some(&range((0, 16), (0, 21))));
+
+    let params = responses[1].get("params").unwrap();
+    let diagnostics = params.get("diagnostics").and_then(Json::as_array).unwrap();
+    assert!(diagnostics.is_empty());
+}
+
+#[test]
+fn publish_syntax_error_at_token() {
+    let responses = session(&[&did_open("let x = 5\nlet y = 6;\n")]);
+    let params = responses[0].get("params").unwrap();
+    let diagnostics = params.get("diagnostics").and_then(Json::as_array).unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].get("range"), Some(&range((1, 0), (1, 3))));
+}
+
+#[test]
+fn hover_expression_type() {
+    let src = "function id(y) {\n    return y;\n}\nlet k = id(5); // Five.\n";
+    let responses = session(&[
+        &did_open(src),
+        &position_request(1, "textDocument/hover", 3, 9),
+        &position_request(2, "textDocument/hover", 3, 12),
+        &position_request(3, "textDocument/hover", 3, 18),
+    ]);
+    assert_eq!(responses.len(), 4);
+
+    let id_hover = responses[1].get("result").unwrap();
+    let contents = id_hover.get("contents").unwrap();
+    assert_eq!(
+        contents.get("value").and_then(Json::as_str),
+        Some("(number) => number")
+    );
+    assert_eq!(id_hover.get("range"), Some(&range((3, 8), (3, 10))));
+
+    let five_hover = responses[2].get("result").unwrap();
+    let contents = five_hover.get("contents").unwrap();
+    assert_eq!(contents.get("value").and_then(Json::as_str), Some("number"));
+
+    // There is no expression within the comment.
+    assert_eq!(responses[3].get("result"), Some(&Json::Null));
+}
+
+#[test]
+fn go_to_definition() {
+    let src = "function id(y) {\n    return y;\n}\nlet k = id(5);\nlet l = k;\n";
+    let responses = session(&[
+        &did_open(src),
+        &position_request(1, "textDocument/definition", 1, 11),
+        &position_request(2, "textDocument/definition", 3, 8),
+        &position_request(3, "textDocument/definition", 4, 8),
+    ]);
+    assert_eq!(responses.len(), 4);
+
+    let arg = responses[1].get("result").unwrap();
+    assert_eq!(arg.get("uri").and_then(Json::as_str), Some("file:///a.ts"));
+    assert_eq!(arg.get("range"), Some(&range((0, 12), (0, 13))));
+
+    let function = responses[2].get("result").unwrap();
+    assert_eq!(function.get("range"), Some(&range((0, 0), (2, 1))));
+
+    let let_stmt = responses[3].get("result").unwrap();
+    assert_eq!(let_stmt.get("range"), Some(&range((3, 0), (3, 14))));
+}
+
+#[test]
+fn unsupported_method() {
+    let responses = session(&[r#"{"jsonrpc": "2.0", "id": 1, "method": "textDocument/rename"}"#]);
+    assert_eq!(responses.len(), 1);
+    let error = responses[0].get("error").unwrap();
+    assert_eq!(error.get("code"), Some(&Json::Number(-32601.0)));
+}
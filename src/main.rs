@@ -1,62 +1,905 @@
 use eqlog_runtime::eqlog_mod;
 use lalrpop_util::lalrpop_mod;
-eqlog_mod!(program);
+eqlog_mod!(
+    #[allow(clippy::all)]
+    program
+);
+mod annotate;
+mod declaration_file;
+mod declarations;
 mod grammar_util;
+mod json;
+mod lsp;
 lalrpop_mod!(grammar);
 #[cfg(test)]
+mod annotate_test;
+#[cfg(test)]
 mod binding_test;
+#[cfg(test)]
+mod declaration_file_test;
 mod error;
 #[cfg(test)]
 mod grammar_test;
 #[cfg(test)]
+mod json_test;
+#[cfg(test)]
+mod lsp_test;
+mod type_printer;
+#[cfg(test)]
+mod type_printer_test;
+#[cfg(test)]
 mod typing_test;
 
-use crate::error::LanguageError;
+use crate::annotate::annotate;
+use crate::declaration_file::declaration_file;
+use crate::declarations::{
+    declarations, resolve, resolve_type_name, type_alias_declarations, Declaration,
+    DeclarationKind, TypeAliasDeclaration,
+};
+use crate::error::{Diagnostic, Location};
 use crate::grammar::ModuleParser;
-use crate::grammar_util::{erase_comments, Literals};
+use crate::grammar_util::{erase_comments, Literals, Span, Spans, SyntaxErrors};
 use crate::program::*;
+use crate::type_printer::{
+    binding_types, type_heads, type_list_heads, TypeHead, TypeListHead, TypePrinter,
+};
 use std::env;
 use std::fs;
+use std::io;
+use std::path::Path;
 use std::process::ExitCode;
 
-fn has_undeclared_variables(p: &Program) -> bool {
-    p.iter_variable_expr_node()
-        .any(|(expr, var)| p.var_type_in_expr(var, expr).is_none())
+/// Returns all nodes that introduce a variable which is already in scope, given by the span of the
+/// node and the variable it introduces.
+fn variable_shadowing(p: &Program, spans: &Spans) -> Vec<(Span, Var)> {
+    let function_var = |func: FunctionNode| -> Var {
+        let (_, var, _, _, _) = p
+            .iter_function()
+            .find(|(func0, _, _, _, _)| *func0 == func)
+            .unwrap();
+        var
+    };
+    let stmts = p.iter_variable_shadowing_stmt().map(|stmt| {
+        let let_var = p
+            .iter_let_stmt_node()
+            .find(|(stmt0, _, _, _)| *stmt0 == stmt)
+            .map(|(_, var, _, _)| var);
+        let for_of_var = || {
+            p.iter_for_of_stmt_node()
+                .find(|(stmt0, _, _, _, _)| *stmt0 == stmt)
+                .map(|(_, var, _, _, _)| var)
+        };
+        let var = let_var.or_else(for_of_var).unwrap_or_else(|| {
+            let (_, func) = p
+                .iter_function_stmt_node()
+                .find(|(stmt0, _)| *stmt0 == stmt)
+                .unwrap();
+            function_var(func)
+        });
+        (spans.stmts[&stmt], var)
+    });
+    let functions = p
+        .iter_variable_shadowing_function()
+        .map(|func| (spans.functions[&func], function_var(func)));
+    let args = p.iter_variable_shadowing_arg().map(|arg| {
+        let (_, var, _, _) = p
+            .iter_cons_arg_list_node()
+            .find(|(arg0, _, _, _)| *arg0 == arg)
+            .unwrap();
+        (spans.args[&arg], var)
+    });
+    // A function statement and the function it contains have the same span.
+    let mut result: Vec<(Span, Var)> = stmts.chain(functions).chain(args).collect();
+    result.sort();
+    result.dedup();
+    result
+}
+
+/// Returns all usages of undeclared variables, given by the span of the variable expression or
+/// assignment statement and the variable.
+fn undeclared_variables(p: &Program, spans: &Spans) -> Vec<(Span, Var)> {
+    let exprs = p
+        .iter_variable_expr_node()
+        .filter(|(expr, var)| p.var_type_in_expr(*var, *expr).is_none())
+        .map(|(expr, var)| (spans.exprs[&expr], var));
+    let assignments = p.iter_cons_stmt_list_node().filter_map(|(stmts, head, _)| {
+        let (_, var, _) = p
+            .iter_assign_stmt_node()
+            .find(|(stmt, _, _)| *stmt == head)?;
+        let is_undeclared = p.var_type_in_stmts(var, stmts).is_none();
+        is_undeclared.then(|| (spans.stmts[&head], var))
+    });
+    let mut result: Vec<(Span, Var)> = exprs.chain(assignments).collect();
+    result.sort();
+    result
+}
+
+/// Records the type alias that each named type node refers to, or that it is unresolved. Returns the
+/// type names that can't be resolved, given by the span of the named type node, the type name and
+/// whether the type name occurs within the definition of the alias it refers to.
+fn resolve_type_names(
+    p: &mut Program,
+    spans: &Spans,
+    decls: &[TypeAliasDeclaration],
+) -> Vec<(Span, TypeName, bool)> {
+    let named: Vec<(TypeNode, TypeName)> = p.iter_named_type_node().collect();
+    let mut unresolved = Vec::new();
+    for (tn, name) in named {
+        let span = spans.types[&tn];
+        match resolve_type_name(decls, name, span.begin) {
+            Some(decl) if decl.span.end <= span.begin => {
+                p.insert_alias_type_node(tn, decl.definition);
+            }
+            decl => {
+                p.insert_unresolved_type_node(tn);
+                unresolved.push((span, name, decl.is_some()));
+            }
+        }
+    }
+    unresolved.sort_by_key(|(span, _, _)| *span);
+    unresolved
+}
+
+/// Returns all `break` and `continue` statements that are not within a loop, given by the span of
+/// the statement and its keyword.
+fn jumps_outside_loops(p: &Program, spans: &Spans) -> Vec<(Span, &'static str)> {
+    let mut result: Vec<(Span, &'static str)> = p
+        .iter_cons_stmt_list_node()
+        .filter(|(stmts, _, _)| !p.in_loop_stmts(*stmts))
+        .filter_map(|(_, head, _)| {
+            let keyword = if p.break_stmt_node(head) {
+                "break"
+            } else if p.continue_stmt_node(head) {
+                "continue"
+            } else {
+                return None;
+            };
+            Some((spans.stmts[&head], keyword))
+        })
+        .collect();
+    result.sort();
+    result
+}
+
+/// Returns all assignments to variables bound by function statements, given by the span of the
+/// assignment statement and the variable.
+fn assignments_to_functions(p: &Program, spans: &Spans) -> Vec<(Span, Var)> {
+    p.iter_assign_to_function_stmt()
+        .map(|stmt| {
+            let (_, var, _) = p
+                .iter_assign_stmt_node()
+                .find(|(stmt0, _, _)| *stmt0 == stmt)
+                .unwrap();
+            (spans.stmts[&stmt], var)
+        })
+        .collect()
+}
+
+/// Returns all field labels that occur more than once in a record literal or record type, given by
+/// the span of the record and the label.
+fn duplicate_fields(p: &Program, spans: &Spans) -> Vec<(Span, Field)> {
+    let expr_fields = p
+        .iter_record_expr_node()
+        .map(|(expr, fields, _)| (spans.exprs[&expr], fields));
+    let type_fields = p
+        .iter_record_type_node()
+        .map(|(ty, fields, _)| (spans.types[&ty], fields));
+    let mut result: Vec<(Span, Field)> = expr_fields
+        .chain(type_fields)
+        .flat_map(|(span, fields)| {
+            p.iter_duplicate_field()
+                .filter(move |(fields0, _)| *fields0 == fields)
+                .map(move |(_, field)| (span, field))
+        })
+        .collect();
+    result.sort();
+    result.dedup();
+    result
+}
+
+/// Returns all assignments to variables introduced by `const` statements, given by the span of the
+/// assignment statement and the declaration of the variable.
+fn assignments_to_consts<'a>(
+    p: &Program,
+    spans: &Spans,
+    decls: &'a [Declaration],
+) -> Vec<(Span, &'a Declaration)> {
+    p.iter_assign_stmt_node()
+        .filter_map(|(stmt, var, _)| {
+            let span = spans.stmts[&stmt];
+            let decl = resolve(decls, var, span.begin)?;
+            (decl.kind == DeclarationKind::Const).then_some((span, decl))
+        })
+        .collect()
+}
+
+/// Returns the spans of all expressions whose type satisfies `pred`, in source order.
+fn expr_spans_with_type(p: &Program, spans: &Spans, pred: impl Fn(Type) -> bool) -> Vec<Span> {
+    let mut result: Vec<Span> = p
+        .iter_expr_type()
+        .filter(|(_, sigma)| pred(*sigma))
+        .map(|(expr, _)| spans.exprs[&expr])
+        .collect();
+    result.sort();
+    result.dedup();
+    result
+}
+
+/// Returns the span of the first expression whose type satisfies `pred`. If there is no such
+/// expression, returns the span of the first type annotation whose semantic type satisfies `pred`,
+/// or else the span of the first function whose type satisfies `pred`.
+fn first_span_with_type(p: &Program, spans: &Spans, pred: impl Fn(Type) -> bool) -> Option<Span> {
+    let expr_span = expr_spans_with_type(p, spans, &pred).first().copied();
+    expr_span
+        .or_else(|| {
+            p.iter_semantic_type()
+                .filter(|(_, sigma)| pred(*sigma))
+                .map(|(tn, _)| spans.types[&tn])
+                .min()
+        })
+        .or_else(|| {
+            p.iter_function_node_type()
+                .filter(|(_, sigma)| pred(*sigma))
+                .map(|(func, _)| spans.functions[&func])
+                .min()
+        })
+}
+
+/// Returns whether `sigma` or one of the types it is composed of satisfies `pred`.
+fn type_contains(p: &Program, sigma: Type, pred: &impl Fn(Type) -> bool) -> bool {
+    let mut visited: Vec<Type> = Vec::new();
+    let mut stack = vec![sigma];
+    while let Some(sigma) = stack.pop() {
+        let sigma = p.root_type(sigma);
+        if visited.contains(&sigma) {
+            continue;
+        }
+        visited.push(sigma);
+        if pred(sigma) {
+            return true;
+        }
+
+        let mut type_lists = Vec::new();
+        for head in type_heads(sigma, p) {
+            match head {
+                TypeHead::Void | TypeHead::Boolean | TypeHead::Number | TypeHead::String => (),
+                TypeHead::Function(dom, cod) => {
+                    type_lists.push(dom);
+                    stack.push(cod);
+                }
+                TypeHead::Array(element) => stack.push(element),
+                TypeHead::Tuple(elements) => type_lists.push(elements),
+                TypeHead::Record(row) => stack.extend(
+                    p.iter_field_type()
+                        .filter(|(rho, _, _)| p.are_equal_row(*rho, row))
+                        .map(|(_, _, tau)| tau),
+                ),
+            }
+        }
+
+        let mut visited_lists: Vec<TypeList> = Vec::new();
+        while let Some(sigmas) = type_lists.pop() {
+            let sigmas = p.root_type_list(sigmas);
+            if visited_lists.contains(&sigmas) {
+                continue;
+            }
+            visited_lists.push(sigmas);
+            for head in type_list_heads(sigmas, p) {
+                if let TypeListHead::Cons(head, tail) = head {
+                    stack.push(head);
+                    type_lists.push(tail);
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Returns the span at which to report a conflict in the types satisfying `pred`.
+///
+/// Conflicts are reported at the first node whose type satisfies `pred` if there is one, and
+/// otherwise at the first node whose type contains such a type.
+fn conflict_span(p: &Program, spans: &Spans, pred: impl Fn(Type) -> bool) -> Span {
+    first_span_with_type(p, spans, &pred)
+        .or_else(|| first_span_with_type(p, spans, |tau| type_contains(p, tau, &pred)))
+        // Every conflict stems from the constraints of some node, so there should always be such
+        // a node. We fall back to the start of the source just in case.
+        .unwrap_or(Span::new(0, 0))
+}
+
+/// If `tau` is a tuple type whose element types end with the type list `sigmas`, returns the
+/// element types that precede `sigmas`.
+fn tuple_prefix(p: &Program, tau: Type, sigmas: TypeList) -> Option<Vec<Type>> {
+    let mut elements = p.tuple_element_types(tau)?;
+    let mut prefix = Vec::new();
+    let mut visited = Vec::new();
+    while !p.are_equal_type_list(elements, sigmas) {
+        elements = p.root_type_list(elements);
+        if visited.contains(&elements) {
+            return None;
+        }
+        visited.push(elements);
+        let (head, tail, _) = p
+            .iter_cons_type_list()
+            .find(|(_, _, cons)| p.are_equal_type_list(*cons, elements))?;
+        prefix.push(head);
+        elements = tail;
+    }
+    Some(prefix)
+}
+
+/// Returns errors for all types which must be equal to two different type operator applications,
+/// for all record types which must both have and lack a field, and for all types which must contain
+/// themselves, each with the span at which it is reported.
+fn conflicting_types(
+    p: &Program,
+    lits: &Literals,
+    spans: &Spans,
+    location: impl Fn(Span) -> Location,
+) -> Vec<(Diagnostic, Span)> {
+    let mut errors = Vec::new();
+    // Nested expressions can begin at the same location, e.g. `a` and `a + b`, but we only
+    // mention each location once.
+    let origin_locations = |origins: Vec<Span>| -> Vec<Location> {
+        let mut origins: Vec<Location> = origins.into_iter().map(&location).collect();
+        origins.dedup();
+        origins
+    };
+
+    for sigma in p.iter_conflicting_type() {
+        let is_sigma = |tau: Type| p.are_equal_type(tau, sigma);
+        let span = conflict_span(p, spans, is_sigma);
+        let origins = expr_spans_with_type(p, spans, is_sigma);
+
+        let heads = type_heads(sigma, p);
+        let mut printer = TypePrinter::new(p, lits);
+        errors.push((
+            Diagnostic::ConflictingTypes {
+                location: location(span),
+                lhs: printer.print_head(sigma, heads[0]),
+                rhs: printer.print_head(sigma, heads[1]),
+                origins: origin_locations(origins),
+            },
+            span,
+        ));
+    }
+
+    // Type lists are only ever equated because they are the domains of function types or the
+    // element types of tuple types, so we report conflicting type lists as conflicting function or
+    // tuple types.
+    for sigmas in p.iter_conflicting_type_list() {
+        let heads = type_list_heads(sigmas, p);
+        let mut printer = TypePrinter::new(p, lits);
+
+        let has_sigmas_dom = |tau: Type| {
+            p.domain_types(tau)
+                .is_some_and(|dom| p.are_equal_type_list(dom, sigmas))
+        };
+        if p.iter_type().any(has_sigmas_dom) {
+            let origins = expr_spans_with_type(p, spans, has_sigmas_dom);
+            let span = conflict_span(p, spans, has_sigmas_dom);
+
+            // Several function types can have the same domain; we print the one of the first
+            // expression involved if there is one.
+            let kappa = p
+                .iter_expr_type()
+                .find(|(expr, tau)| spans.exprs[expr] == span && has_sigmas_dom(*tau))
+                .map(|(_, tau)| tau)
+                .or_else(|| p.iter_type().find(|tau| has_sigmas_dom(*tau)))
+                .unwrap();
+            let cod = p.codomain_type(kappa).unwrap();
+
+            let lhs_dom = printer.type_list_with_head(heads[0]);
+            let rhs_dom = printer.type_list_with_head(heads[1]);
+            errors.push((
+                Diagnostic::ConflictingTypes {
+                    location: location(span),
+                    lhs: printer.print_function(&lhs_dom, cod),
+                    rhs: printer.print_function(&rhs_dom, cod),
+                    origins: origin_locations(origins),
+                },
+                span,
+            ));
+            continue;
+        }
+
+        // Tuples of different lengths conflict in a suffix of their element types.
+        let has_sigmas_suffix = |tau: Type| tuple_prefix(p, tau, sigmas).is_some();
+        let origins = expr_spans_with_type(p, spans, has_sigmas_suffix);
+        let span = conflict_span(p, spans, has_sigmas_suffix);
+        let prefix = p
+            .iter_expr_type()
+            .filter(|(expr, _)| spans.exprs[expr] == span)
+            .map(|(_, tau)| tau)
+            .chain(p.iter_type())
+            .find_map(|tau| tuple_prefix(p, tau, sigmas))
+            // Should the type list not be part of a tuple type after all, we report the
+            // conflicting type lists as tuple types of their own.
+            .unwrap_or_default();
+
+        // The tail of a cons cell that is also nil can be the conflicting type list itself.
+        let elements = |head: TypeListHead| -> Vec<Type> {
+            let mut result = prefix.clone();
+            if let TypeListHead::Cons(head, tail) = head {
+                result.push(head);
+                if !p.are_equal_type_list(tail, sigmas) {
+                    result.extend(printer.type_list(tail));
+                }
+            }
+            result
+        };
+        let lhs = elements(heads[0]);
+        let rhs = elements(heads[1]);
+        errors.push((
+            Diagnostic::ConflictingTypes {
+                location: location(span),
+                lhs: printer.print_tuple(&lhs),
+                rhs: printer.print_tuple(&rhs),
+                origins: origin_locations(origins),
+            },
+            span,
+        ));
+    }
+
+    // A field that a record type must both have and lack is reported at the first access of the
+    // field if there is one.
+    for (rho, field) in p.iter_missing_field() {
+        let is_record = |tau: Type| {
+            p.record_row(tau)
+                .is_some_and(|rho0| p.are_equal_row(rho0, rho))
+        };
+        let access_span = p
+            .iter_field_access_expr_node()
+            .filter(|(_, record, field0)| {
+                *field0 == field && is_record(p.expr_type(*record).unwrap())
+            })
+            .map(|(expr, _, _)| spans.exprs[&expr])
+            .min();
+        let span = access_span.unwrap_or_else(|| conflict_span(p, spans, is_record));
+        let origins = expr_spans_with_type(p, spans, is_record);
+        let (_, kappa) = p
+            .iter_record_type()
+            .find(|(rho0, _)| p.are_equal_row(*rho0, rho))
+            .unwrap();
+        errors.push((
+            Diagnostic::MissingField {
+                location: location(span),
+                ty: TypePrinter::new(p, lits).print(kappa),
+                field: lits.field_name(field).to_string(),
+                origins: origin_locations(origins),
+            },
+            span,
+        ));
+    }
+
+    // Once a type list is both nil and cons, every type is considered structurally smaller than
+    // every other type, so infinite types are only meaningful if there are no other conflicts.
+    let infinite_types: Vec<Type> = match errors.is_empty() {
+        true => p.iter_infinite_type().collect(),
+        false => Vec::new(),
+    };
+    for sigma in infinite_types {
+        let is_sigma = |tau: Type| p.are_equal_type(tau, sigma);
+        let span = conflict_span(p, spans, is_sigma);
+        let origins = expr_spans_with_type(p, spans, is_sigma);
+        errors.push((
+            Diagnostic::InfiniteType {
+                location: location(span),
+                ty: TypePrinter::new(p, lits).print(sigma),
+                origins: origin_locations(origins),
+            },
+            span,
+        ));
+    }
+
+    errors
+}
+
+/// Returns the spans of all `+` expressions for which we could not infer whether they add numbers
+/// or concatenate strings.
+fn unresolved_additions(p: &Program, spans: &Spans) -> Vec<Span> {
+    p.iter_add_expr_node()
+        .filter(|(expr, _, _)| {
+            !p.number_addition_expr_node(*expr) && !p.string_concatenation_expr_node(*expr)
+        })
+        .map(|(expr, _, _)| spans.exprs[&expr])
+        .collect()
+}
+
+/// Returns all types that must be comparable but whose values are known to not be comparable. Each
+/// type is given together with the span of the `==` or `!=` expression whose operand type it is or,
+/// for instances of comparable-required types of polymorphic variables, of the variable expression
+/// that is instantiated.
+fn incomparable_types(p: &Program, spans: &Spans) -> Vec<(Span, Type)> {
+    let is_incomparable =
+        |sigma: Type| !type_heads(sigma, p).is_empty() && !p.comparable_type(sigma);
+    let equalities = p.iter_equality_expr_node().filter_map(|(expr, lhs, _)| {
+        let sigma = p.expr_type(lhs).unwrap();
+        is_incomparable(sigma).then(|| (spans.exprs[&expr], sigma))
+    });
+    // Instances of types that are already incomparable have been reported at the equality
+    // expression or at an earlier instantiation.
+    let instances = p.iter_expr_instantiation().flat_map(|(expr, instance)| {
+        p.iter_instantiate()
+            .filter(move |(instance0, sigma, _)| {
+                p.are_equal_instantiation(*instance0, instance)
+                    && p.requires_comparable(*sigma)
+                    && !is_incomparable(*sigma)
+            })
+            .filter(move |(_, _, tau)| is_incomparable(*tau))
+            .map(move |(_, _, tau)| (spans.exprs[&expr], tau))
+    });
+    let mut result: Vec<(Span, Type)> = equalities.chain(instances).collect();
+    result.sort();
+    result.dedup();
+    result
+}
+
+/// Returns the value of `index` if it is a non-negative integer literal expression.
+fn literal_index(p: &Program, lits: &Literals, index: ExprNode) -> Option<usize> {
+    let (_, num) = p
+        .iter_number_literal_expr_node()
+        .find(|(index0, _)| *index0 == index)?;
+    let (s, _) = lits.numbers.iter().find(|(_, num0)| **num0 == num)?;
+    s.parse().ok()
+}
+
+/// Equates the types of index expressions on tuples with the element types at their index, and
+/// returns whether this added any equalities.
+///
+/// The program can't compute with number literals, so we look up the element types here and
+/// close the program again until no new equalities are found.
+fn index_tuples(p: &mut Program, lits: &Literals) -> bool {
+    let equalities: Vec<(Type, Type)> = p
+        .iter_index_expr_node()
+        .filter(|(expr, _, _)| p.tuple_index_expr_node(*expr))
+        .filter_map(|(expr, tuple, index)| {
+            let elements = p.tuple_element_types(p.expr_type(tuple).unwrap())?;
+            let elements = TypePrinter::new(p, lits).type_list(elements);
+            let element = *elements.get(literal_index(p, lits, index)?)?;
+            let expr_ty = p.expr_type(expr).unwrap();
+            (!p.are_equal_type(expr_ty, element)).then_some((expr_ty, element))
+        })
+        .collect();
+    for (expr_ty, element) in &equalities {
+        p.equate_type(*expr_ty, *element);
+    }
+    !equalities.is_empty()
+}
+
+/// Closes the program, including the types of index expressions on tuples.
+fn close_program(p: &mut Program, lits: &Literals) {
+    p.close();
+    while index_tuples(p, lits) {
+        p.close();
+    }
+}
+
+/// Returns the spans of all array literals of polymorphic type that are resolved as both arrays and
+/// tuples by different instances of their type.
+fn array_and_tuple_literals(p: &Program, spans: &Spans) -> Vec<Span> {
+    let is_polymorphic = |sigma: Type| {
+        p.iter_instantiate().any(|(_, sigma0, tau)| {
+            p.are_equal_type(sigma0, sigma) && !p.are_equal_type(tau, sigma)
+        })
+    };
+    let mut result: Vec<Span> = p
+        .iter_array_expr_node()
+        .filter(|(expr, _)| p.array_literal_expr_node(*expr) && p.tuple_literal_expr_node(*expr))
+        .filter(|(expr, _)| p.expr_type(*expr).is_some_and(is_polymorphic))
+        .map(|(expr, _)| spans.exprs[&expr])
+        .collect();
+    result.sort();
+    result
 }
 
-fn has_undetermined_type(p: &Program) -> bool {
-    p.iter_type().any(|sigma| !p.determined_type(sigma))
+/// Returns the spans of all index expressions on tuples whose index is not an integer literal less
+/// than the length of the tuple, together with the type of the tuple.
+fn invalid_tuple_indices(p: &Program, spans: &Spans, lits: &Literals) -> Vec<(Span, Type)> {
+    p.iter_index_expr_node()
+        .filter(|(expr, _, _)| p.tuple_index_expr_node(*expr))
+        .filter_map(|(expr, tuple, index)| {
+            let sigma = p.expr_type(tuple).unwrap();
+            let len = TypePrinter::new(p, lits)
+                .type_list(p.tuple_element_types(sigma)?)
+                .len();
+            let is_valid = literal_index(p, lits, index).is_some_and(|n| n < len);
+            (!is_valid).then(|| (spans.exprs[&expr], sigma))
+        })
+        .collect()
 }
 
-fn check_source(src: &str) -> Result<(Program, Literals, ModuleNode), LanguageError> {
+/// Returns the spans of the first expressions of each undetermined type.
+fn undetermined_types(p: &Program, spans: &Spans) -> Vec<Span> {
+    let undetermined: Vec<Type> = p
+        .iter_type()
+        .filter(|sigma| !p.determined_type(*sigma))
+        .collect();
+    let mut result: Vec<Span> = undetermined
+        .iter()
+        .filter_map(|sigma| {
+            let is_sigma = |tau: Type| p.are_equal_type(tau, *sigma);
+            first_span_with_type(p, spans, is_sigma)
+        })
+        .collect();
+
+    // Undetermined types that don't belong to any node can stem from nodes that the parser
+    // discarded when recovering from a syntax error. Otherwise, we report them at the beginning of
+    // the module.
+    if result.is_empty() && !undetermined.is_empty() && spans.syntax_errors.is_empty() {
+        result.push(Span::new(0, 0));
+    }
+
+    result.sort();
+    result.dedup();
+    result
+}
+
+/// A parsed module together with the closed program model.
+type CheckedModule = (Program, Literals, Spans, ModuleNode);
+
+/// Parses and type checks a module. Returns the module unless the parser could not recover from a
+/// syntax error, and all diagnostics ordered by location, each with the span of source it refers
+/// to.
+fn check_module(src: &str) -> (Option<CheckedModule>, Vec<(Diagnostic, Span)>) {
     let no_comments_src = erase_comments(src);
 
     let mut p = Program::new();
     let mut lits = Literals::new();
+    let mut spans = Spans::new();
+
+    let mut syntax_errors = SyntaxErrors::new();
+
+    let result = ModuleParser::new().parse(
+        &mut p,
+        &mut lits,
+        &mut spans,
+        &mut syntax_errors,
+        &no_comments_src,
+    );
 
-    let module = ModuleParser::new()
-        .parse(&mut p, &mut lits, &no_comments_src)
-        .map_err(|err| LanguageError::from_parse_error(err, &no_comments_src))?;
+    // Syntax errors the parser has recovered from are reported along with all other diagnostics.
+    let mut diagnostics: Vec<(Diagnostic, Span)> = syntax_errors
+        .into_iter()
+        .map(|recovery| {
+            let span = Diagnostic::parse_error_span(&recovery.error, &no_comments_src);
+            let diagnostic = Diagnostic::from_parse_error(recovery.error, &no_comments_src);
+            (diagnostic, span)
+        })
+        .collect();
 
+    let module = match result {
+        Ok(module) => module,
+        Err(err) => {
+            let span = Diagnostic::parse_error_span(&err, &no_comments_src);
+            diagnostics.push((Diagnostic::from_parse_error(err, &no_comments_src), span));
+            return (None, diagnostics);
+        }
+    };
+
+    // The parsed nodes are only visible once the program is closed, so we close it once before
+    // resolving type names and again to take the aliases into account.
     p.close();
+    let type_decls = type_alias_declarations(&p, &spans);
+    let unresolved_type_names = resolve_type_names(&mut p, &spans, &type_decls);
+    close_program(&mut p, &lits);
+
+    // Array literals and index expressions whose types don't determine whether they apply to arrays
+    // or tuples apply to arrays.
+    let unresolved_literals: Vec<ExprNode> = p
+        .iter_array_expr_node()
+        .map(|(expr, _)| expr)
+        .filter(|expr| !p.array_literal_expr_node(*expr) && !p.tuple_literal_expr_node(*expr))
+        .collect();
+    let unresolved_indices: Vec<ExprNode> = p
+        .iter_index_expr_node()
+        .map(|(expr, _, _)| expr)
+        .filter(|expr| !p.array_index_expr_node(*expr) && !p.tuple_index_expr_node(*expr))
+        .collect();
+    for expr in unresolved_literals {
+        p.insert_array_literal_expr_node(expr);
+    }
+    for expr in unresolved_indices {
+        p.insert_array_index_expr_node(expr);
+    }
+    close_program(&mut p, &lits);
+
+    let location = |span: Span| Location::from_offset(&no_comments_src, span.begin);
+
+    let decls = declarations(&p, &spans);
+    for (span, var) in variable_shadowing(&p, &spans) {
+        // `declarations` mirrors the VarTypeInX axioms, so it should find the shadowed declaration.
+        // If it doesn't, we still report the shadowing, just without the previous location.
+        let previous = resolve(&decls, var, span.begin).map(|decl| location(decl.span));
+        diagnostics.push((
+            Diagnostic::VariableShadowing {
+                name: lits.var_name(var).to_string(),
+                location: location(span),
+                previous,
+            },
+            span,
+        ));
+    }
+
+    for (span, var) in undeclared_variables(&p, &spans) {
+        diagnostics.push((
+            Diagnostic::UndeclaredVariable {
+                name: lits.var_name(var).to_string(),
+                location: location(span),
+            },
+            span,
+        ));
+    }
+
+    for decl in &type_decls {
+        if let Some(previous) = resolve_type_name(&type_decls, decl.name, decl.span.begin) {
+            diagnostics.push((
+                Diagnostic::TypeAliasShadowing {
+                    name: lits.type_name_str(decl.name).to_string(),
+                    location: location(decl.span),
+                    previous: location(previous.span),
+                },
+                decl.span,
+            ));
+        }
+    }
+
+    for (span, name, is_recursive) in unresolved_type_names {
+        let name = lits.type_name_str(name).to_string();
+        let location = location(span);
+        let diagnostic = match is_recursive {
+            true => Diagnostic::RecursiveTypeAlias { name, location },
+            false => Diagnostic::UndeclaredType { name, location },
+        };
+        diagnostics.push((diagnostic, span));
+    }
+
+    for (span, keyword) in jumps_outside_loops(&p, &spans) {
+        diagnostics.push((
+            Diagnostic::JumpOutsideLoop {
+                keyword: keyword.to_string(),
+                location: location(span),
+            },
+            span,
+        ));
+    }
 
-    if p.variable_shadowing() {
-        return Err(LanguageError::VariableShadowing);
+    for (span, decl) in assignments_to_consts(&p, &spans, &decls) {
+        diagnostics.push((
+            Diagnostic::AssignToConst {
+                name: lits.var_name(decl.var).to_string(),
+                location: location(span),
+                declaration: location(decl.span),
+            },
+            span,
+        ));
     }
 
-    if has_undeclared_variables(&p) {
-        return Err(LanguageError::UndeclaredVariable);
+    for (span, var) in assignments_to_functions(&p, &spans) {
+        diagnostics.push((
+            Diagnostic::AssignmentToFunction {
+                name: lits.var_name(var).to_string(),
+                location: location(span),
+            },
+            span,
+        ));
     }
 
-    if p.conflicting_types() {
-        return Err(LanguageError::ConflictingTypes);
+    for (span, field) in duplicate_fields(&p, &spans) {
+        diagnostics.push((
+            Diagnostic::DuplicateField {
+                field: lits.field_name(field).to_string(),
+                location: location(span),
+            },
+            span,
+        ));
     }
 
-    if has_undetermined_type(&p) {
-        return Err(LanguageError::UndeterminedType);
+    // An array literal that is both an array and a tuple equates its array type with its tuple type
+    // in every instance, so the resulting type conflicts are only consequences of the literal.
+    let array_and_tuple_literals = array_and_tuple_literals(&p, &spans);
+    for span in &array_and_tuple_literals {
+        diagnostics.push((
+            Diagnostic::ArrayAndTupleLiteral {
+                location: location(*span),
+            },
+            *span,
+        ));
+    }
+    if array_and_tuple_literals.is_empty() {
+        diagnostics.extend(conflicting_types(&p, &lits, &spans, location));
+    }
+
+    for (span, sigma) in incomparable_types(&p, &spans) {
+        diagnostics.push((
+            Diagnostic::IncomparableType {
+                location: location(span),
+                ty: TypePrinter::new(&p, &lits).print(sigma),
+            },
+            span,
+        ));
+    }
+
+    for (span, sigma) in invalid_tuple_indices(&p, &spans, &lits) {
+        diagnostics.push((
+            Diagnostic::InvalidTupleIndex {
+                location: location(span),
+                ty: TypePrinter::new(&p, &lits).print(sigma),
+            },
+            span,
+        ));
+    }
+
+    for span in unresolved_additions(&p, &spans) {
+        diagnostics.push((
+            Diagnostic::UnresolvedOverload {
+                operator: "+".to_string(),
+                location: location(span),
+            },
+            span,
+        ));
+    }
+
+    // Usages of undeclared variables and expressions involved in type conflicts typically also
+    // have undetermined types, so we only report undetermined types at other locations.
+    let mut reported: Vec<Location> = diagnostics.iter().map(|(d, _)| d.location()).collect();
+    for span in undetermined_types(&p, &spans) {
+        let location = location(span);
+        if !reported.contains(&location) {
+            reported.push(location);
+            diagnostics.push((Diagnostic::UndeterminedType { location }, span));
+        }
+    }
+
+    // Nodes within source skipped by the parser are not part of the module, so we don't report
+    // semantic errors located there or mention them as origins of type errors. The same goes for
+    // nodes the parser creates from the remainder of a statement with a syntax error, e.g. the `x`
+    // in `let 53x;`, so skipped source that doesn't end a statement or begin a block extends to the
+    // next token that does.
+    let is_boundary = |c: char| matches!(c, ';' | '{' | '}');
+    let skipped: Vec<(Location, Location)> = spans
+        .syntax_errors
+        .iter()
+        .map(|span| {
+            let skipped_src = &no_comments_src[span.begin..span.end];
+            let end = match skipped_src.trim_end().ends_with(is_boundary) {
+                true => span.end,
+                false => no_comments_src[span.end..]
+                    .find(is_boundary)
+                    .map_or(no_comments_src.len(), |i| span.end + i),
+            };
+            (
+                location(*span),
+                Location::from_offset(&no_comments_src, end),
+            )
+        })
+        .collect();
+    let is_skipped = |loc: Location| {
+        skipped
+            .iter()
+            .any(|(begin, end)| *begin <= loc && loc < *end)
+    };
+    diagnostics
+        .retain(|(d, _)| matches!(d, Diagnostic::ParseError { .. }) || !is_skipped(d.location()));
+    for (d, _) in &mut diagnostics {
+        if let Diagnostic::ConflictingTypes { origins, .. }
+        | Diagnostic::InfiniteType { origins, .. }
+        | Diagnostic::MissingField { origins, .. } = d
+        {
+            origins.retain(|origin| !is_skipped(*origin));
+        }
     }
 
-    Ok((p, lits, module))
+    diagnostics.sort_by_key(|(d, _)| d.location());
+    (Some((p, lits, spans, module)), diagnostics)
+}
+
+/// Parses and type checks a module. On failure, returns all diagnostics ordered by location.
+fn check_source(src: &str) -> Result<CheckedModule, Vec<Diagnostic>> {
+    match check_module(src) {
+        (Some(checked), diagnostics) if diagnostics.is_empty() => Ok(checked),
+        (_, diagnostics) => Err(diagnostics.into_iter().map(|(d, _)| d).collect()),
+    }
 }
 
 fn main() -> ExitCode {
@@ -65,10 +908,38 @@ fn main() -> ExitCode {
     // The first argument is the path to this executable; we ignore it.
     args.next();
 
-    let file_name: String = match args.next() {
+    let usage = "Usage: ts [--print-types] [--declaration] [--annotate] <FILE_NAME> | ts --lsp";
+    let mut print_types = false;
+    let mut emit_declaration = false;
+    let mut print_annotated = false;
+    let mut file_name: Option<String> = None;
+    for arg in args {
+        match arg.as_str() {
+            "--print-types" => print_types = true,
+            "--declaration" => emit_declaration = true,
+            "--annotate" => print_annotated = true,
+            "--lsp" => {
+                // The language server communicates via stdin and stdout.
+                return match lsp::run(io::stdin().lock(), io::stdout().lock()) {
+                    Ok(()) => ExitCode::SUCCESS,
+                    Err(err) => {
+                        eprintln!("Language server error: {err}");
+                        ExitCode::FAILURE
+                    }
+                };
+            }
+            _ if file_name.is_none() => file_name = Some(arg),
+            _ => {
+                eprintln!("{usage}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let file_name: String = match file_name {
         Some(file_name) => file_name,
         None => {
-            eprintln!("Usage: ts <FILE_NAME>");
+            eprintln!("{usage}");
             return ExitCode::FAILURE;
         }
     };
@@ -81,13 +952,36 @@ fn main() -> ExitCode {
         }
     };
 
-    match check_source(&src) {
-        Ok(_) => {}
-        Err(err) => {
-            eprintln!("{}", err);
+    let (p, lits, spans, module) = match check_source(&src) {
+        Ok(checked) => checked,
+        Err(diagnostics) => {
+            for diagnostic in diagnostics {
+                eprintln!("{file_name}: {diagnostic}");
+            }
             return ExitCode::FAILURE;
         }
     };
 
+    if print_types {
+        for (name, ty) in binding_types(module, &p, &lits) {
+            println!("{name}: {ty}");
+        }
+    }
+
+    if print_annotated {
+        print!("{}", annotate(&src, &p, &lits, &spans));
+    }
+
+    if emit_declaration {
+        // Writes `foo.d.ts` for `foo.ts`.
+        let declaration_file_name = Path::new(&file_name).with_extension("d.ts");
+        let contents = declaration_file(module, &p, &lits);
+        if let Err(err) = fs::write(&declaration_file_name, contents) {
+            let declaration_file_name = declaration_file_name.display();
+            eprintln!("Error writing file {declaration_file_name}: {err}");
+            return ExitCode::FAILURE;
+        }
+    }
+
     ExitCode::SUCCESS
 }
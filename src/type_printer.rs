@@ -0,0 +1,561 @@
+use crate::grammar_util::Literals;
+use crate::program::*;
+
+use std::collections::{HashMap, HashSet};
+
+/// A type operator application that a Type element is equal to.
+///
+/// In programs without type errors, every Type element is equal to at most one type operator
+/// application. Type elements which are not equal to any type operator application are unsolved
+/// type variables.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TypeHead {
+    Void,
+    Boolean,
+    Number,
+    String,
+    Function(TypeList, Type),
+    Array(Type),
+    Tuple(TypeList),
+    Record(Row),
+}
+
+/// A type list operator application that a TypeList element is equal to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TypeListHead {
+    Nil,
+    Cons(Type, TypeList),
+}
+
+/// Returns all type operator applications that `sigma` is equal to.
+pub fn type_heads(sigma: Type, p: &Program) -> Vec<TypeHead> {
+    let is_sigma = |tau: Option<Type>| tau.is_some_and(|tau| p.are_equal_type(tau, sigma));
+
+    let mut heads = Vec::new();
+    if is_sigma(p.void_type()) {
+        heads.push(TypeHead::Void);
+    }
+    if is_sigma(p.boolean_type()) {
+        heads.push(TypeHead::Boolean);
+    }
+    if is_sigma(p.number_type()) {
+        heads.push(TypeHead::Number);
+    }
+    if is_sigma(p.string_type()) {
+        heads.push(TypeHead::String);
+    }
+    if let (Some(dom), Some(cod)) = (p.domain_types(sigma), p.codomain_type(sigma)) {
+        heads.push(TypeHead::Function(dom, cod));
+    }
+    if let Some(element) = p.element_type(sigma) {
+        heads.push(TypeHead::Array(element));
+    }
+    if let Some(elements) = p.tuple_element_types(sigma) {
+        heads.push(TypeHead::Tuple(elements));
+    }
+    if let Some(row) = p.record_row(sigma) {
+        heads.push(TypeHead::Record(row));
+    }
+    heads
+}
+
+/// Returns whether a row is open, i.e. whether further fields can be added to it by unification.
+///
+/// Open rows play the role of row variables: A record type with an open row that is not in the type
+/// context of a polymorphic function is instantiated into a fresh open row at each use. We render
+/// such record types as type parameters bounded by the known fields, e.g. `<a extends { name: b },
+/// b>(o: a) => b`.
+pub fn is_open_row(row: Row, p: &Program) -> bool {
+    !p.iter_closed_row()
+        .any(|(row0, _)| p.are_equal_row(row0, row))
+}
+
+/// Returns all type list operator applications that `sigmas` is equal to.
+pub fn type_list_heads(sigmas: TypeList, p: &Program) -> Vec<TypeListHead> {
+    let mut heads = Vec::new();
+    if p.nil_type_list()
+        .is_some_and(|nil| p.are_equal_type_list(nil, sigmas))
+    {
+        heads.push(TypeListHead::Nil);
+    }
+    if let Some((head, tail, _)) = p
+        .iter_cons_type_list()
+        .find(|(_, _, cons)| p.are_equal_type_list(*cons, sigmas))
+    {
+        heads.push(TypeListHead::Cons(head, tail));
+    }
+    heads
+}
+
+/// Returns the names of the arguments in an arg list.
+pub fn arg_names(mut args: ArgListNode, p: &Program, lits: &Literals) -> Vec<String> {
+    let mut result = Vec::new();
+    while let Some((_, var, _, tail)) = p
+        .iter_cons_arg_list_node()
+        .find(|(args0, _, _, _)| *args0 == args)
+    {
+        result.push(lits.var_name(var).to_string());
+        args = tail;
+    }
+    result
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BindingKind {
+    Let,
+    Const,
+    Function,
+}
+
+/// A variable introduced by a top-level statement of a module.
+#[derive(Clone, Debug)]
+pub struct Binding {
+    pub kind: BindingKind,
+    pub var: Var,
+    pub ty: GeneralType,
+    /// The names of the arguments if the variable is bound to a function literal.
+    pub arg_names: Option<Vec<String>>,
+    /// The type context of the module, relative to which the type of the binding is quantified.
+    pub ctx: TypeContext,
+}
+
+/// Returns the variables introduced by the top-level `let`, `const` and `function` statements of a
+/// module, in source order.
+pub fn module_bindings(module: ModuleNode, p: &Program, lits: &Literals) -> Vec<Binding> {
+    let (_, mut stmts) = p
+        .iter_module()
+        .find(|(module0, _)| *module0 == module)
+        .expect("module node should have a statement list");
+    let ctx = p.stmts_type_context(stmts).unwrap();
+    let function_args = |func: FunctionNode| -> (Var, Vec<String>) {
+        let (_, var, args, _, _) = p
+            .iter_function()
+            .find(|(func0, _, _, _, _)| *func0 == func)
+            .unwrap();
+        (var, arg_names(args, p, lits))
+    };
+    let const_stmts: HashSet<StmtNode> = p
+        .iter_const_stmt_node()
+        .map(|(stmt, _, _, _)| stmt)
+        .collect();
+
+    let mut result = Vec::new();
+    while let Some((_, head, tail)) = p
+        .iter_cons_stmt_list_node()
+        .find(|(stmts0, _, _)| *stmts0 == stmts)
+    {
+        let let_binding = p
+            .iter_let_stmt_node()
+            .find(|(stmt, _, _, _)| *stmt == head)
+            .map(|(_, var, _, expr)| {
+                let arg_names = p
+                    .iter_function_expr_node()
+                    .find(|(expr0, _)| *expr0 == expr)
+                    .map(|(_, func)| function_args(func).1);
+                Binding {
+                    kind: match const_stmts.contains(&head) {
+                        true => BindingKind::Const,
+                        false => BindingKind::Let,
+                    },
+                    var,
+                    ty: p.mono_type(p.expr_type(expr).unwrap()).unwrap(),
+                    arg_names,
+                    ctx,
+                }
+            });
+        let function_binding = p
+            .iter_function_stmt_node()
+            .find(|(stmt, _)| *stmt == head)
+            .map(|(_, func)| {
+                let (var, arg_names) = function_args(func);
+                Binding {
+                    kind: BindingKind::Function,
+                    var,
+                    ty: p.poly_type(p.function_node_type(func).unwrap()).unwrap(),
+                    arg_names: Some(arg_names),
+                    ctx,
+                }
+            });
+        result.extend(let_binding.or(function_binding));
+        stmts = tail;
+    }
+    result
+}
+
+/// Returns the names and rendered types of the variables introduced by the top-level `let` and
+/// `function` statements of a module, in source order.
+pub fn binding_types(module: ModuleNode, p: &Program, lits: &Literals) -> Vec<(String, String)> {
+    module_bindings(module, p, lits)
+        .into_iter()
+        .map(|binding| {
+            let ty = TypePrinter::new(p, lits).print_general(
+                binding.ty,
+                binding.ctx,
+                binding.arg_names.as_deref(),
+            );
+            (lits.var_name(binding.var).to_string(), ty)
+        })
+        .collect()
+}
+
+/// Renders semantic types in TypeScript syntax, e.g. `(number, string) => boolean`.
+///
+/// Unsolved type variables are named `a`, `b`, ... in the order in which they are first printed,
+/// so that the same type variable is printed with the same name if it occurs in several types
+/// printed with the same TypePrinter. Record types with open rows that have been quantified are
+/// printed by name as well. Parts of infinite types (which can only occur in programs with type
+/// errors) are printed as `...`.
+pub struct TypePrinter<'a> {
+    p: &'a Program,
+    lits: &'a Literals,
+    var_names: HashMap<Type, String>,
+    // The record types with open rows that are printed as type parameters.
+    bounded_vars: HashSet<Type>,
+    // The types we're currently printing, to detect infinite types.
+    stack: Vec<Type>,
+    positional_arg_names: bool,
+}
+
+impl<'a> TypePrinter<'a> {
+    pub fn new(p: &'a Program, lits: &'a Literals) -> Self {
+        TypePrinter {
+            p,
+            lits,
+            var_names: HashMap::new(),
+            bounded_vars: HashSet::new(),
+            stack: Vec::new(),
+            positional_arg_names: false,
+        }
+    }
+
+    /// Prints the arguments of function types without known argument names as `arg0: number`
+    /// instead of `number`. TypeScript itself requires argument names in function types.
+    pub fn with_positional_arg_names(mut self) -> Self {
+        self.positional_arg_names = true;
+        self
+    }
+
+    fn var_name(&mut self, sigma: Type) -> String {
+        let index = self.var_names.len();
+        self.var_names
+            .entry(sigma)
+            .or_insert_with(|| {
+                let letter = char::from(b'a' + (index % 26) as u8);
+                match index / 26 {
+                    0 => letter.to_string(),
+                    n => format!("{letter}{n}"),
+                }
+            })
+            .clone()
+    }
+
+    /// Renders a general type as seen from type context `ctx`, e.g. `<a>(x: a) => a`.
+    ///
+    /// The type variables of a PolyType which are not in `ctx` are universally quantified. If
+    /// `arg_names` is given and the type is a function type with as many arguments, the arguments
+    /// are printed with these names.
+    pub fn print_general(
+        &mut self,
+        ty: GeneralType,
+        ctx: TypeContext,
+        arg_names: Option<&[String]>,
+    ) -> String {
+        let (sigma, is_poly) = self.general_type_parts(ty);
+        // Quantified record types are printed by name in the body, so we print the quantifiers
+        // first.
+        let quantifiers = match is_poly {
+            true => self.quantifiers(sigma, ctx),
+            false => String::new(),
+        };
+        let body = match (self.function_head(sigma), arg_names) {
+            (Some((dom, cod)), Some(arg_names)) if dom.len() == arg_names.len() => {
+                let (args, cod) = self.print_named_args(sigma, &dom, arg_names, cod);
+                format!("({args}) => {cod}")
+            }
+            _ => self.print(sigma),
+        };
+        format!("{quantifiers}{body}")
+    }
+
+    /// Renders the signature of a function declaration with the given type as seen from type
+    /// context `ctx`, e.g. `<a>(x: a): a`.
+    ///
+    /// Panics if the type is not a function type with as many arguments as `arg_names`.
+    pub fn print_signature(
+        &mut self,
+        ty: GeneralType,
+        ctx: TypeContext,
+        arg_names: &[String],
+    ) -> String {
+        let (sigma, is_poly) = self.general_type_parts(ty);
+        let (dom, cod) = self
+            .function_head(sigma)
+            .filter(|(dom, _)| dom.len() == arg_names.len())
+            .expect("function should have a function type with matching arity");
+        let quantifiers = match is_poly {
+            true => self.quantifiers(sigma, ctx),
+            false => String::new(),
+        };
+        let (args, cod) = self.print_named_args(sigma, &dom, arg_names, cod);
+        format!("{quantifiers}({args}): {cod}")
+    }
+
+    /// Returns the type underlying a general type and whether the general type is a PolyType.
+    fn general_type_parts(&self, ty: GeneralType) -> (Type, bool) {
+        let p = self.p;
+        let poly = p
+            .iter_poly_type()
+            .find(|(_, ty0)| p.are_equal_general_type(*ty0, ty));
+        if let Some((sigma, _)) = poly {
+            return (sigma, true);
+        }
+        let (sigma, _) = p
+            .iter_mono_type()
+            .find(|(_, ty0)| p.are_equal_general_type(*ty0, ty))
+            .expect("general type should be either a MonoType or a PolyType");
+        (sigma, false)
+    }
+
+    /// Renders the arguments and the codomain of the function type `sigma` with domain types
+    /// `dom`, codomain type `cod` and argument names `arg_names`.
+    fn print_named_args(
+        &mut self,
+        sigma: Type,
+        dom: &[Type],
+        arg_names: &[String],
+        cod: Type,
+    ) -> (String, String) {
+        let sigma = self.p.root_type(sigma);
+        self.stack.push(sigma);
+        let args: Vec<String> = arg_names
+            .iter()
+            .zip(dom)
+            .map(|(name, arg)| format!("{name}: {}", self.print(*arg)))
+            .collect();
+        let cod = self.print(cod);
+        self.stack.pop();
+        (args.join(", "), cod)
+    }
+
+    /// Renders the type parameter list `<a, b>` of the type variables in `sigma` which are not in
+    /// `ctx`, or the empty string if there are none. Record types with open rows are rendered as
+    /// bounded type parameters `a extends { name: b }`, and subsequently printed by name.
+    pub fn quantifiers(&mut self, sigma: Type, ctx: TypeContext) -> String {
+        let p = self.p;
+        let vars: Vec<Type> = self
+            .type_vars(sigma)
+            .into_iter()
+            .filter(|var| !p.type_in_context(*var, ctx))
+            .collect();
+        // All type parameters must be named before printing bounds, since the bound of an open
+        // record can refer to open records quantified after it, e.g. `{ inner: b }`.
+        for var in &vars {
+            self.var_name(*var);
+            if p.record_row(*var).is_some() {
+                self.bounded_vars.insert(*var);
+            }
+        }
+        let quantified: Vec<String> = vars
+            .into_iter()
+            .map(|var| match p.record_row(var) {
+                Some(row) => {
+                    let name = self.var_name(var);
+                    let bound = self.print_head(var, TypeHead::Record(row));
+                    format!("{name} extends {bound}")
+                }
+                None => self.var_name(var),
+            })
+            .collect();
+        match quantified.as_slice() {
+            [] => String::new(),
+            _ => format!("<{}>", quantified.join(", ")),
+        }
+    }
+
+    /// Returns the domain and codomain types of a function type that isn't part of a type error.
+    fn function_head(&self, sigma: Type) -> Option<(Vec<Type>, Type)> {
+        match type_heads(sigma, self.p).as_slice() {
+            [TypeHead::Function(dom, cod)] => Some((self.type_list(*dom), *cod)),
+            _ => None,
+        }
+    }
+
+    /// Returns the unsolved type variables and record types with open rows occuring in a type, in
+    /// the order in which they are printed.
+    fn type_vars(&self, sigma: Type) -> Vec<Type> {
+        let mut vars = Vec::new();
+        let mut visited = Vec::new();
+        let mut stack = vec![sigma];
+        while let Some(sigma) = stack.pop() {
+            let sigma = self.p.root_type(sigma);
+            if visited.contains(&sigma) {
+                continue;
+            }
+            visited.push(sigma);
+            match type_heads(sigma, self.p).first() {
+                Some(TypeHead::Function(dom, cod)) => {
+                    // Push in reverse so that the domain types are visited first.
+                    stack.push(*cod);
+                    stack.extend(self.type_list(*dom).into_iter().rev());
+                }
+                Some(TypeHead::Array(element)) => stack.push(*element),
+                Some(TypeHead::Tuple(elements)) => {
+                    stack.extend(self.type_list(*elements).into_iter().rev());
+                }
+                Some(TypeHead::Record(row)) => {
+                    if is_open_row(*row, self.p) {
+                        vars.push(sigma);
+                    }
+                    let fields = self.record_fields(*row);
+                    stack.extend(fields.into_iter().rev().map(|(_, tau)| tau));
+                }
+                Some(_) => {}
+                None => vars.push(sigma),
+            }
+        }
+        vars
+    }
+
+    /// Renders a type.
+    pub fn print(&mut self, sigma: Type) -> String {
+        let sigma = self.p.root_type(sigma);
+        if self.bounded_vars.contains(&sigma) {
+            return self.var_name(sigma);
+        }
+        match type_heads(sigma, self.p).first() {
+            Some(head) => self.print_head(sigma, *head),
+            None => self.var_name(sigma),
+        }
+    }
+
+    /// Renders a type as the given type operator application, which must be among the
+    /// `type_heads` of `sigma`.
+    pub fn print_head(&mut self, sigma: Type, head: TypeHead) -> String {
+        let sigma = self.p.root_type(sigma);
+        if self.stack.contains(&sigma) {
+            return "...".to_string();
+        }
+
+        match head {
+            TypeHead::Void => "void".to_string(),
+            TypeHead::Boolean => "boolean".to_string(),
+            TypeHead::Number => "number".to_string(),
+            TypeHead::String => "string".to_string(),
+            TypeHead::Function(dom, cod) => {
+                self.stack.push(sigma);
+                let dom = self.type_list(dom);
+                let result = self.print_function(&dom, cod);
+                self.stack.pop();
+                result
+            }
+            TypeHead::Array(element) => {
+                self.stack.push(sigma);
+                let is_function = matches!(
+                    type_heads(element, self.p).first(),
+                    Some(TypeHead::Function(_, _))
+                );
+                let element = self.print(element);
+                self.stack.pop();
+                // Function types must be parenthesized since `() => number[]` is a function
+                // returning an array.
+                match is_function {
+                    true => format!("({element})[]"),
+                    false => format!("{element}[]"),
+                }
+            }
+            TypeHead::Tuple(elements) => {
+                self.stack.push(sigma);
+                let elements = self.type_list(elements);
+                let result = self.print_tuple(&elements);
+                self.stack.pop();
+                result
+            }
+            TypeHead::Record(row) => {
+                self.stack.push(sigma);
+                let fields: Vec<String> = self
+                    .record_fields(row)
+                    .into_iter()
+                    .map(|(name, tau)| format!("{name}: {}", self.print(tau)))
+                    .collect();
+                self.stack.pop();
+                match fields.as_slice() {
+                    [] => "{}".to_string(),
+                    _ => format!("{{ {} }}", fields.join("; ")),
+                }
+            }
+        }
+    }
+
+    /// Returns the names and types of the fields of a row ordered by name, omitting fields that the
+    /// row must lack (which is a type error).
+    pub fn record_fields(&self, row: Row) -> Vec<(&'a str, Type)> {
+        let p = self.p;
+        let row = p.root_row(row);
+        let mut fields: Vec<(&'a str, Type)> = p
+            .iter_field_type()
+            .filter(|(row0, field, _)| p.root_row(*row0) == row && !p.row_lacks_field(row, *field))
+            .map(|(_, field, tau)| (self.lits.field_name(field), tau))
+            .collect();
+        fields.sort_by_key(|(name, _)| *name);
+        fields.dedup_by_key(|(name, _)| *name);
+        fields
+    }
+
+    /// Renders a tuple type with the given element types.
+    pub fn print_tuple(&mut self, elements: &[Type]) -> String {
+        let elements: Vec<String> = elements.iter().map(|sigma| self.print(*sigma)).collect();
+        format!("[{}]", elements.join(", "))
+    }
+
+    /// Renders a function type with the given domain and codomain types.
+    pub fn print_function(&mut self, dom: &[Type], cod: Type) -> String {
+        let dom: Vec<String> = dom
+            .iter()
+            .enumerate()
+            .map(|(i, sigma)| match self.positional_arg_names {
+                true => format!("arg{i}: {}", self.print(*sigma)),
+                false => self.print(*sigma),
+            })
+            .collect();
+        let cod = self.print(cod);
+        format!("({}) => {}", dom.join(", "), cod)
+    }
+
+    /// Returns the types in a type list whose first cell is considered to be equal to the given
+    /// type list operator application.
+    pub fn type_list_with_head(&self, head: TypeListHead) -> Vec<Type> {
+        match head {
+            TypeListHead::Nil => Vec::new(),
+            TypeListHead::Cons(head, tail) => {
+                let mut result = vec![head];
+                result.extend(self.type_list(tail));
+                result
+            }
+        }
+    }
+
+    /// Returns the types in a type list.
+    pub fn type_list(&self, sigmas: TypeList) -> Vec<Type> {
+        let mut result = Vec::new();
+        let mut visited = Vec::new();
+        let mut sigmas = self.p.root_type_list(sigmas);
+        while !visited.contains(&sigmas) {
+            visited.push(sigmas);
+            // If the list cell is both nil and cons (which is a type error), we print the longer list.
+            let cons = type_list_heads(sigmas, self.p)
+                .into_iter()
+                .find_map(|head| match head {
+                    TypeListHead::Cons(head, tail) => Some((head, tail)),
+                    TypeListHead::Nil => None,
+                });
+            match cons {
+                Some((head, tail)) => {
+                    result.push(head);
+                    sigmas = self.p.root_type_list(tail);
+                }
+                None => break,
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,241 @@
+use crate::check_source;
+use crate::program::*;
+use crate::type_printer::{arg_names, binding_types, TypePrinter};
+
+use indoc::indoc;
+
+/// Renders the type of the top-level function `name` as seen from the module.
+fn function_type(name: &str, src: &str) -> String {
+    let (p, lits, _, module) = check_source(src).unwrap();
+    let (_, stmts) = p.iter_module().find(|(mn, _)| *mn == module).unwrap();
+    let ctx = p.stmts_type_context(stmts).unwrap();
+
+    let var: Var = *lits.vars.get(name).expect("variable should be in literals");
+    let (func, _, args, _, _) = p
+        .iter_function()
+        .find(|(_, var0, _, _, _)| *var0 == var)
+        .expect("function should exist");
+    let ty = p.poly_type(p.function_node_type(func).unwrap()).unwrap();
+    let arg_names = arg_names(args, &p, &lits);
+    TypePrinter::new(&p, &lits).print_general(ty, ctx, Some(&arg_names))
+}
+
+#[test]
+fn print_annotated_function() {
+    let ty = function_type(
+        "foo",
+        indoc! {"
+            function foo(x: number, y: string): boolean {
+                return true;
+            }
+        "},
+    );
+    assert_eq!(ty, "(x: number, y: string) => boolean");
+}
+
+#[test]
+fn print_void_function() {
+    let ty = function_type(
+        "foo",
+        indoc! {"
+            function foo() {}
+        "},
+    );
+    assert_eq!(ty, "() => void");
+}
+
+#[test]
+fn print_identity() {
+    let ty = function_type(
+        "id",
+        indoc! {"
+            function id(y) {
+                return y;
+            }
+        "},
+    );
+    assert_eq!(ty, "<a>(y: a) => a");
+}
+
+#[test]
+fn print_higher_order_function() {
+    let ty = function_type(
+        "apply",
+        indoc! {"
+            function apply(f, x) {
+                return f(x);
+            }
+        "},
+    );
+    assert_eq!(ty, "<a, b>(f: (a) => b, x: a) => b");
+}
+
+#[test]
+fn print_returned_closure() {
+    let ty = function_type(
+        "konst",
+        indoc! {"
+            function konst(x) {
+                function inner(y: number) {
+                    return x;
+                }
+                return inner;
+            }
+        "},
+    );
+    assert_eq!(ty, "<a>(x: a) => (number) => a");
+}
+
+#[test]
+fn print_instantiated_function() {
+    let ty = function_type(
+        "id_number",
+        indoc! {"
+            function id_number(x) {
+                if (true) {
+                    return x;
+                } else {
+                    return 5;
+                }
+            }
+        "},
+    );
+    assert_eq!(ty, "(x: number) => number");
+}
+
+#[test]
+fn print_array_types() {
+    let ty = function_type(
+        "map",
+        indoc! {"
+            function map(f, xs) {
+                let ys = [];
+                for (const x of xs) {
+                    ys = [f(x)];
+                }
+                return ys;
+            }
+        "},
+    );
+    assert_eq!(ty, "<a, b>(f: (a) => b, xs: a[]) => b[]");
+
+    let ty = function_type(
+        "adders",
+        indoc! {"
+            function adders(x: number) {
+                return [function add(y: number) { return x + y; }];
+            }
+        "},
+    );
+    assert_eq!(ty, "(x: number) => ((number) => number)[]");
+}
+
+#[test]
+fn print_tuple_types() {
+    let ty = function_type(
+        "constant",
+        indoc! {"
+            function constant(x) {
+                return function ignore(t: [number, string[]]) {
+                    return x;
+                };
+            }
+        "},
+    );
+    assert_eq!(ty, "<a>(x: a) => ([number, string[]]) => a");
+
+    let ty = function_type(
+        "unit",
+        indoc! {"
+            function unit(t: []): [[], number] {
+                return [t, 0];
+            }
+        "},
+    );
+    assert_eq!(ty, "(t: []) => [[], number]");
+}
+
+#[test]
+fn print_record_types() {
+    let ty = function_type(
+        "rename",
+        indoc! {"
+            function rename(person: { name: string; age: number }, name) {
+                return { name: name, age: person.age };
+            }
+        "},
+    );
+    assert_eq!(
+        ty,
+        "<a>(person: { age: number; name: string }, name: a) => { age: number; name: a }"
+    );
+}
+
+#[test]
+fn print_row_polymorphic_types() {
+    let ty = function_type(
+        "getName",
+        indoc! {"
+            function getName(o) {
+                return o.name;
+            }
+        "},
+    );
+    assert_eq!(ty, "<a extends { name: b }, b>(o: a) => b");
+
+    let ty = function_type(
+        "birthday",
+        indoc! {"
+            function birthday(o) {
+                o.age + 1;
+                return o;
+            }
+        "},
+    );
+    assert_eq!(ty, "<a extends { age: number }>(o: a) => a");
+}
+
+#[test]
+fn print_nested_row_polymorphic_types() {
+    let ty = function_type(
+        "getInnerName",
+        indoc! {"
+            function getInnerName(o) {
+                return o.inner.name;
+            }
+        "},
+    );
+    assert_eq!(
+        ty,
+        "<a extends { inner: b }, b extends { name: c }, c>(o: a) => c"
+    );
+}
+
+#[test]
+fn print_binding_types() {
+    let (p, lits, _, module) = check_source(indoc! {"
+        let k: number = 5;
+        function id(y) {
+            return y;
+        }
+        let the_five = id(5);
+        let f = function g(x: string) {
+            return x == 'abc';
+        };
+        id(true);
+    "})
+    .unwrap();
+    let types: Vec<String> = binding_types(module, &p, &lits)
+        .into_iter()
+        .map(|(name, ty)| format!("{name}: {ty}"))
+        .collect();
+    assert_eq!(
+        types,
+        [
+            "k: number",
+            "id: <a>(y: a) => a",
+            "the_five: number",
+            "f: (x: string) => boolean",
+        ]
+    );
+}
@@ -1,4 +1,5 @@
 use crate::check_source;
+use crate::error::display_diagnostics;
 use crate::grammar_util::*;
 use crate::program::*;
 
@@ -13,7 +14,7 @@ fn var_has_type(var: &str, ty: Type, p: &Program, lits: &Literals) -> bool {
 
 #[test]
 fn void_literal() {
-    let (p, lits, _) = check_source(&indoc! {"
+    let (p, lits, _, _) = check_source(&indoc! {"
         let b = ();
     "})
     .unwrap();
@@ -23,7 +24,7 @@ fn void_literal() {
 
 #[test]
 fn number_literal() {
-    let (p, lits, _) = check_source(&indoc! {"
+    let (p, lits, _, _) = check_source(&indoc! {"
         let b = 5;
     "})
     .unwrap();
@@ -33,7 +34,7 @@ fn number_literal() {
 
 #[test]
 fn string_literal() {
-    let (p, lits, _) = check_source(&indoc! {"
+    let (p, lits, _, _) = check_source(&indoc! {"
         let b = 'xyz';
     "})
     .unwrap();
@@ -43,7 +44,7 @@ fn string_literal() {
 
 #[test]
 fn boolean_true() {
-    let (p, lits, _) = check_source(&indoc! {"
+    let (p, lits, _, _) = check_source(&indoc! {"
         let b = true;
     "})
     .unwrap();
@@ -53,7 +54,7 @@ fn boolean_true() {
 
 #[test]
 fn boolean_false() {
-    let (p, lits, _) = check_source(&indoc! {"
+    let (p, lits, _, _) = check_source(&indoc! {"
         let b = false;
     "})
     .unwrap();
@@ -67,7 +68,10 @@ fn bad_let_boolean_number_expr() {
         let b: boolean = 5;
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Conflicting type constraints");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:18: `boolean` and `number`"
+    );
 }
 
 #[test]
@@ -76,12 +80,15 @@ fn bad_let_number_string_expr() {
         let b: number = 'xyz';
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Conflicting type constraints");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:17: `number` and `string`"
+    );
 }
 
 #[test]
 fn let_variable_variable() {
-    let (p, lits, _) = check_source(&indoc! {"
+    let (p, lits, _, _) = check_source(&indoc! {"
         let a = 4;
         let b = a;
     "})
@@ -92,7 +99,7 @@ fn let_variable_variable() {
 
 #[test]
 fn if_cond() {
-    let (p, lits, _) = check_source(&indoc! {"
+    let (p, lits, _, _) = check_source(&indoc! {"
         function xyz (a) {
             if (a) {
             } else {
@@ -106,7 +113,7 @@ fn if_cond() {
 
 #[test]
 fn while_cond() {
-    let (p, lits, _) = check_source(&indoc! {"
+    let (p, lits, _, _) = check_source(&indoc! {"
         function xyz (a) {
             while (a) {}
         };
@@ -118,7 +125,7 @@ fn while_cond() {
 
 #[test]
 fn equals_expr_type() {
-    let (p, lits, _) = check_source(&indoc! {"
+    let (p, lits, _, _) = check_source(&indoc! {"
         let a = 5 == 5;
     "})
     .unwrap();
@@ -132,12 +139,295 @@ fn bad_equals_arg_types() {
         let a = 5 == 'xyz';
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Conflicting type constraints");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:9: `number` and `string` (involving expressions at 1:9, 1:14)"
+    );
+}
+
+#[test]
+fn arithmetic_expr_type() {
+    let (p, lits, _, _) = check_source(&indoc! {"
+        function f(x, y) {
+            return (x + 1) * y % 3 - x / y;
+        }
+        let a = f(2, 3);
+        let b = a-1 == -2 + 1;
+    "})
+    .unwrap();
+    let number_type = p.number_type().unwrap();
+    let boolean_type = p.boolean_type().unwrap();
+    assert!(var_has_type("a", number_type, &p, &lits));
+    assert!(var_has_type("b", boolean_type, &p, &lits));
+}
+
+#[test]
+fn bad_arithmetic_operand_types() {
+    let err = check_source(&indoc! {"
+        let a = 'abc';
+        let b = 5 * a;
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:9: `number` and `string` (involving expressions at 1:9, 2:9, 2:13)"
+    );
+}
+
+#[test]
+fn bad_arithmetic_result_type() {
+    let err = check_source(&indoc! {"
+        let a: boolean = 1 - 2;
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:18: `boolean` and `number` (involving expressions at 1:18, 1:22)"
+    );
+}
+
+#[test]
+fn addition_expr_type() {
+    let (p, lits, _, _) = check_source(&indoc! {"
+        function greet(name) {
+            return 'Hello, ' + name;
+        }
+        function increment(x) {
+            return x + 1;
+        }
+        function concat(x, y) {
+            let z: string = x + y;
+            return z;
+        }
+        let a = greet('World');
+        let b = increment(1) + increment(2);
+        let c = concat('a', 'b') + a;
+    "})
+    .unwrap();
+    let number_type = p.number_type().unwrap();
+    let string_type = p.string_type().unwrap();
+    assert!(var_has_type("a", string_type, &p, &lits));
+    assert!(var_has_type("b", number_type, &p, &lits));
+    assert!(var_has_type("c", string_type, &p, &lits));
+}
+
+#[test]
+fn bad_addition_operand_types() {
+    let err = check_source(&indoc! {"
+        let a = 1 + 'abc';
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:9: `number` and `string` (involving expressions at 1:9, 1:13)"
+    );
+}
+
+#[test]
+fn bad_unresolved_addition() {
+    let err = check_source(&indoc! {"
+        function add(x, y) {
+            return x + y;
+        }
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Cannot infer whether `+` at 2:12 applies to numbers or strings"
+    );
+}
+
+#[test]
+fn bad_addition_non_addable_operands() {
+    let err = check_source(&indoc! {"
+        let a = true + false;
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:9: `boolean` and `number` (involving expressions at 1:9, 1:16)"
+    );
+}
+
+#[test]
+fn comparison_and_logical_expr_type() {
+    let (p, lits, _, _) = check_source(&indoc! {"
+        function in_range(x, lo, hi) {
+            return lo <= x && x < hi || !(x != hi);
+        }
+        let a = in_range(1, 0, 2);
+        let b = 1 + 2 * 3 > 6 == !a;
+    "})
+    .unwrap();
+    let boolean_type = p.boolean_type().unwrap();
+    assert!(var_has_type("a", boolean_type, &p, &lits));
+    assert!(var_has_type("b", boolean_type, &p, &lits));
+}
+
+#[test]
+fn bad_comparison_operand_types() {
+    let err = check_source(&indoc! {"
+        let a = 'abc' < 'abd';
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:9: `number` and `string` (involving expressions at 1:9, 1:17)"
+    );
+}
+
+#[test]
+fn bad_logical_operand_types() {
+    let err = check_source(&indoc! {"
+        let a = 5;
+        let b = !a || true;
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:9: `boolean` and `number` (involving expressions at 1:9, 2:9, 2:10, 2:15)"
+    );
+}
+
+#[test]
+fn polymorphic_equality() {
+    let (p, lits, _, _) = check_source(&indoc! {"
+        function eq(x, y) {
+            return x == y;
+        }
+        let a = eq('abc', 'abc') != eq((), ());
+    "})
+    .unwrap();
+    let boolean_type = p.boolean_type().unwrap();
+    assert!(var_has_type("a", boolean_type, &p, &lits));
+}
+
+#[test]
+fn bad_equals_function_types() {
+    let err = check_source(&indoc! {"
+        function f(x: number): number {
+            return x;
+        }
+        let a = f == f;
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Cannot compare values of type `(number) => number` at 4:9"
+    );
+}
+
+#[test]
+fn bad_equals_instantiated_function_types() {
+    // The operand types of the comparison in `eq` are type variables, which are only instantiated
+    // into incomparable types where `eq` and `ne` are used.
+    let err = check_source(&indoc! {"
+        function eq(x, y) {
+            return x == y;
+        }
+        function ne(x, y) {
+            return !eq(x, y);
+        }
+        function f(a: number) {
+            return a;
+        }
+        let b = eq(f, f);
+        let c = ne([1], [2]);
+        let d = eq(1, 2);
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Cannot compare values of type `(number) => number` at 10:9
+            Cannot compare values of type `number[]` at 11:9"}
+    );
+}
+
+#[test]
+fn bad_not_equals_inferred_function_types() {
+    // The operand types are only known to be function types by the application of `g` after the
+    // comparison.
+    let err = check_source(&indoc! {"
+        function test(g, h) {
+            let a = g != h;
+            return g(5);
+        }
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Cannot compare values of type `(number) => a` at 2:13"
+    );
+}
+
+#[test]
+fn assignment() {
+    let (p, lits, _, _) = check_source(&indoc! {"
+        function count(n) {
+            let i = 0;
+            while (i < n) {
+                i = i + 1;
+            }
+            n = i;
+            return i;
+        }
+        let a = 0;
+        a = count(5);
+    "})
+    .unwrap();
+    let number_type = p.number_type().unwrap();
+    assert!(var_has_type("a", number_type, &p, &lits));
+}
+
+#[test]
+fn bad_assignment_value_type() {
+    let err = check_source(&indoc! {"
+        let a = 5;
+        a = 'abc';
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:9: `number` and `string` (involving expressions at 1:9, 2:5)"
+    );
+}
+
+#[test]
+fn bad_assignment_to_undeclared_variable() {
+    let err = check_source(&indoc! {"
+        function foo() {
+            a = 5;
+        }
+        let a = 3;
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Usage of undeclared variable `a` at 2:5"
+    );
+}
+
+#[test]
+fn bad_assignment_to_function() {
+    let err = check_source(&indoc! {"
+        function foo(x) {
+            return x;
+        }
+        foo = function bar(y) {
+            return y;
+        };
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Cannot assign to function `foo` at 4:1"
+    );
 }
 
 #[test]
 fn app_dom_cod_to_func() {
-    let (p, lits, _) = check_source(&indoc! {"
+    let (p, lits, _, _) = check_source(&indoc! {"
         function xyz (a) {
             let b: boolean = a('asdf', 123);
         };
@@ -159,7 +449,7 @@ fn app_dom_cod_to_func() {
 
 #[test]
 fn app_func_to_dom_cod() {
-    let (p, lits, _) = check_source(&indoc! {"
+    let (p, lits, _, _) = check_source(&indoc! {"
         function xyz (a, b, c) {
             let d: boolean = a('asdf', 123);
             let e = a(b, c);
@@ -177,7 +467,7 @@ fn app_func_to_dom_cod() {
 
 #[test]
 fn determined_return_type_by_call_site() {
-    let (p, lits, _) = check_source(&indoc! {"
+    let (p, lits, _, _) = check_source(&indoc! {"
         function asdf () {
           return asdf();
         }
@@ -200,7 +490,10 @@ fn bad_conflicting_return_types() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Conflicting type constraints");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 3:12: `number` and `string` (involving expressions at 3:12, 5:12)"
+    );
 }
 
 #[test]
@@ -214,7 +507,602 @@ fn bad_conflicting_return_types_implicit() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Conflicting type constraints");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 3:12: `void` and `string`"
+    );
+}
+
+#[test]
+fn bad_conflicting_return_types_missing_else() {
+    let err = check_source(&indoc! {"
+        function asdf (cond) {
+          if (cond) {
+            return 'asdf';
+          } else if (!cond) {
+            return 'fdsa';
+          }
+        }
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 3:12: `void` and `string` (involving expressions at 3:12, 5:12)"
+    );
+}
+
+#[test]
+fn infinite_loop_return_type() {
+    let (p, lits, _, _) = check_source(&indoc! {"
+        function first_above(n) {
+            let i = 0;
+            while (true) {
+                i = i + 1;
+                if (i <= n) {
+                    continue;
+                }
+                return i;
+            }
+        }
+        let x = first_above(5);
+    "})
+    .unwrap();
+    let number_type = p.number_type().unwrap();
+    assert!(var_has_type("x", number_type, &p, &lits));
+}
+
+#[test]
+fn bad_conflicting_return_types_break() {
+    let err = check_source(&indoc! {"
+        function asdf (cond) {
+          while (true) {
+            if (cond) {
+              break;
+            }
+            return 5;
+          }
+        }
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 6:12: `void` and `number`"
+    );
+}
+
+#[test]
+fn literal_condition_return_type() {
+    let (p, lits, _, _) = check_source(&indoc! {"
+        function one(cond) {
+            if (cond || true) {
+                return 1;
+            }
+        }
+        function two() {
+            if (!true) {
+                return 1;
+            } else {
+                return 2;
+            }
+        }
+        function three(cond) {
+            while (!false && true) {
+                if (false) {
+                    break;
+                }
+                if (cond) {
+                    return 3;
+                }
+            }
+        }
+        let x = one(true);
+        let y = two();
+        let z = three(false);
+    "})
+    .unwrap();
+    let number_type = p.number_type().unwrap();
+    assert!(var_has_type("x", number_type, &p, &lits));
+    assert!(var_has_type("y", number_type, &p, &lits));
+    assert!(var_has_type("z", number_type, &p, &lits));
+}
+
+#[test]
+fn bad_conflicting_return_types_unknown_condition() {
+    let err = check_source(&indoc! {"
+        function asdf (cond) {
+          if (cond && true) {
+            return 5;
+          }
+        }
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 3:12: `void` and `number`"
+    );
+}
+
+#[test]
+fn for_loop_types() {
+    let (p, lits, _, _) = check_source(&indoc! {"
+        function sum_below(n) {
+            let sum = 0;
+            for (let i = 0; i < n; i = i + 1) {
+                sum = sum + i;
+            }
+            return sum;
+        }
+        function find(pred) {
+            let i = 0;
+            for (; true; i = i + 1) {
+                if (pred(i)) {
+                    return i;
+                }
+            }
+        }
+        let x = sum_below(10);
+        let y = find(function above(j) { return j > 5; });
+    "})
+    .unwrap();
+    let number_type = p.number_type().unwrap();
+    assert!(var_has_type("x", number_type, &p, &lits));
+    assert!(var_has_type("y", number_type, &p, &lits));
+}
+
+#[test]
+fn bad_for_condition_type() {
+    let err = check_source(&indoc! {"
+        for (let i = 0; i; i = i + 1) {}
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:14: `boolean` and `number` (involving expressions at 1:14, 1:17, 1:24, 1:28)"
+    );
+}
+
+#[test]
+fn array_types() {
+    let (p, lits, _, _) = check_source(&indoc! {"
+        function sum(xs) {
+            let total = 0;
+            for (let i = 0; i < 3; i = i + 1) {
+                total = total + xs[i];
+            }
+            return total;
+        }
+        function first(xs) {
+            return xs[0];
+        }
+        let names: string[] = [];
+        let grid = [[1, 2], [3]];
+        let a = sum(grid[1]);
+        let b = first(names);
+        let c = first([true, false]);
+    "})
+    .unwrap();
+    let number_type = p.number_type().unwrap();
+    let string_type = p.string_type().unwrap();
+    let boolean_type = p.boolean_type().unwrap();
+    assert!(var_has_type("a", number_type, &p, &lits));
+    assert!(var_has_type("b", string_type, &p, &lits));
+    assert!(var_has_type("c", boolean_type, &p, &lits));
+}
+
+#[test]
+fn tuple_types() {
+    let (p, lits, _, _) = check_source(&indoc! {"
+        function swap(pair: [number, string]): [string, number] {
+            return [pair[1], pair[0]];
+        }
+        function flag(x: number) {
+            const result: [number, boolean] = [x, true];
+            return result;
+        }
+        let t: [string, number] = swap([1, 'a']);
+        let a = t[0];
+        let b = t[1];
+        let c = flag(b)[1];
+    "})
+    .unwrap();
+    let number_type = p.number_type().unwrap();
+    let string_type = p.string_type().unwrap();
+    let boolean_type = p.boolean_type().unwrap();
+    assert!(var_has_type("a", string_type, &p, &lits));
+    assert!(var_has_type("b", number_type, &p, &lits));
+    assert!(var_has_type("c", boolean_type, &p, &lits));
+}
+
+#[test]
+fn polymorphic_tuple_types() {
+    let (p, lits, _, _) = check_source(&indoc! {"
+        function constant(x) {
+            return function ignore(t: [number, string]) {
+                return x;
+            };
+        }
+        let a = constant(true)([1, 'a']);
+        let b = constant('s')([2, 'b']);
+    "})
+    .unwrap();
+    let string_type = p.string_type().unwrap();
+    let boolean_type = p.boolean_type().unwrap();
+    assert!(var_has_type("a", boolean_type, &p, &lits));
+    assert!(var_has_type("b", string_type, &p, &lits));
+}
+
+#[test]
+fn polymorphic_tuple_literals() {
+    let (p, lits, _, _) = check_source(&indoc! {"
+        function pair(x, y) {
+            return [x, y];
+        }
+        let t: [number, string] = pair(1, 'a');
+        let a = t[1];
+    "})
+    .unwrap();
+    let string_type = p.string_type().unwrap();
+    assert!(var_has_type("a", string_type, &p, &lits));
+}
+
+#[test]
+fn bad_polymorphic_tuple_literal_as_array() {
+    let err = check_source(&indoc! {"
+        function pair(x, y) {
+            return [x, y];
+        }
+        let t: [number, string] = pair(1, 'a');
+        let xs: number[] = pair(1, 2);
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Array literal at 2:12 can't be used both as an array and as a tuple"
+    );
+}
+
+#[test]
+fn bad_tuple_length() {
+    let err = check_source(&indoc! {"
+        let t: [number, string] = [1, 'a', true];
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:27: `[number, string]` and `[number, string, boolean]`"
+    );
+}
+
+#[test]
+fn bad_tuple_element_type() {
+    let err = check_source(&indoc! {"
+        let t: [number, string] = [1, 2];
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:28: `number` and `string` (involving expressions at 1:28, 1:31)"
+    );
+}
+
+#[test]
+fn bad_tuple_index_out_of_range() {
+    let err = check_source(&indoc! {"
+        function f(t: [number, string]) {
+            return t[2];
+        }
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Tuple of type `[number, string]` at 2:12 must be indexed by an integer literal less than its length"
+    );
+}
+
+#[test]
+fn bad_tuple_index_not_literal() {
+    let err = check_source(&indoc! {"
+        function f(t: [number, string], i: number) {
+            return t[i];
+        }
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Tuple of type `[number, string]` at 2:12 must be indexed by an integer literal less than its length"
+    );
+}
+
+#[test]
+fn bad_array_tuple_type() {
+    let err = check_source(&indoc! {"
+        let xs: number[] = [1];
+        let t: [number] = xs;
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:20: `number[]` and `[number]` (involving expressions at 1:20, 2:19)"
+    );
+}
+
+#[test]
+fn record_types() {
+    let (p, lits, _, _) = check_source(&indoc! {"
+        function age(person: { name: string; age: number }) {
+            return person.age;
+        }
+        function older(person) {
+            return { age: person.age + 1, name: person.name };
+        }
+        let alice = { name: 'Alice', age: 30 };
+        let a = age(older(alice));
+        let b = older(alice).name;
+        let c = { inner: { flag: true } }.inner.flag;
+    "})
+    .unwrap();
+    let number_type = p.number_type().unwrap();
+    let string_type = p.string_type().unwrap();
+    let boolean_type = p.boolean_type().unwrap();
+    assert!(var_has_type("a", number_type, &p, &lits));
+    assert!(var_has_type("b", string_type, &p, &lits));
+    assert!(var_has_type("c", boolean_type, &p, &lits));
+}
+
+#[test]
+fn bad_missing_field() {
+    let err = check_source(&indoc! {"
+        let alice = { name: 'Alice' };
+        let a = alice.age;
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Record type `{ name: string }` at 2:9 has no field `age` (involving expressions at 1:13, 2:9)"
+    );
+}
+
+#[test]
+fn bad_record_fields_mismatch() {
+    let err = check_source(&indoc! {"
+        let person: { name: string } = { name: 'Alice', age: 30 };
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Record type `{ name: string }` at 1:32 has no field `age`"
+    );
+}
+
+#[test]
+fn bad_record_field_type() {
+    let err = check_source(&indoc! {"
+        let person: { name: string; age: number } = { name: 'Alice', age: '30' };
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:53: `number` and `string` (involving expressions at 1:53, 1:67)"
+    );
+}
+
+#[test]
+fn bad_duplicate_fields() {
+    let err = check_source(&indoc! {"
+        let r = { a: 1, b: true, a: 'x' };
+        let s: string = r.a;
+        let t: { a: number; a: string } = { a: 'y' };
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Field `a` declared more than once in record at 1:9
+            Field `a` declared more than once in record at 3:8"}
+    );
+}
+
+#[test]
+fn bad_field_access_non_record() {
+    let err = check_source(&indoc! {"
+        let x = 5;
+        let y = x.length;
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:9: `number` and `{ length: a }` (involving expressions at 1:9, 2:9)"
+    );
+}
+
+#[test]
+fn bad_infinite_record_type() {
+    let err = check_source(&indoc! {"
+        function f(x) {
+            return x.next == x;
+        }
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Infinite type at 2:12: `{ next: ... }` (involving expressions at 2:12, 2:22)
+            Cannot compare values of type `{ next: ... }` at 2:12"}
+    );
+}
+
+#[test]
+fn row_polymorphic_types() {
+    let (p, lits, _, _) = check_source(&indoc! {"
+        function getName(o) {
+            return o.name;
+        }
+        function birthday(o) {
+            o.age + 1;
+            return o;
+        }
+        let a = getName({ name: 'a' });
+        let b = getName({ name: 'b', age: 1 });
+        let c = birthday({ name: 'c', age: 2 }).name;
+        let d = getName(birthday({ name: true, age: 3 }));
+    "})
+    .unwrap();
+    let string_type = p.string_type().unwrap();
+    let boolean_type = p.boolean_type().unwrap();
+    assert!(var_has_type("a", string_type, &p, &lits));
+    assert!(var_has_type("b", string_type, &p, &lits));
+    assert!(var_has_type("c", string_type, &p, &lits));
+    assert!(var_has_type("d", boolean_type, &p, &lits));
+}
+
+#[test]
+fn bad_row_polymorphic_missing_field() {
+    let err = check_source(&indoc! {"
+        function getName(o) {
+            return o.name;
+        }
+        let a = getName({ age: 1 });
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Record type `{ age: number }` at 4:17 has no field `name`"
+    );
+}
+
+#[test]
+fn bad_undetermined_row() {
+    let err = check_source(&indoc! {"
+        function getName(o) {
+            return o.name;
+        }
+        let g = getName;
+    "})
+    .unwrap_err();
+    assert_eq!(&display_diagnostics(&err), "Undetermined type at 4:9");
+}
+
+#[test]
+fn for_of_types() {
+    let (p, lits, _, _) = check_source(&indoc! {"
+        function join(words) {
+            let result = '';
+            for (const word of words) {
+                result = result + word;
+            }
+            return result;
+        }
+        let s = join(['a', 'b']);
+    "})
+    .unwrap();
+    let string_type = p.string_type().unwrap();
+    assert!(var_has_type("s", string_type, &p, &lits));
+}
+
+#[test]
+fn bad_array_element_types() {
+    let err = check_source(&indoc! {"
+        let xs = [1, 'abc'];
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:11: `number` and `string` (involving expressions at 1:11, 1:14)"
+    );
+}
+
+#[test]
+fn bad_index_type() {
+    let err = check_source(&indoc! {"
+        let xs = [1, 2];
+        let x = xs['abc'];
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:11: `number` and `string` (involving expressions at 1:11, 1:14, 2:9, 2:12)"
+    );
+}
+
+#[test]
+fn bad_index_non_array() {
+    let err = check_source(&indoc! {"
+        let n = 5;
+        let x = n[0];
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:9: `number` and `a[]` (involving expressions at 1:9, 2:9, 2:11)"
+    );
+}
+
+#[test]
+fn bad_addition_array_operands() {
+    let err = check_source(&indoc! {"
+        let xs = ['a', 'b'];
+        let ys = xs + xs;
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:10: `number` and `string[]` (involving expressions at 1:10, 2:10, 2:15)"
+    );
+}
+
+#[test]
+fn bad_undetermined_empty_array() {
+    let err = check_source(&indoc! {"
+        let xs = [];
+    "})
+    .unwrap_err();
+    assert_eq!(&display_diagnostics(&err), "Undetermined type at 1:10");
+}
+
+#[test]
+fn bad_infinite_array_type() {
+    let err = check_source(&indoc! {"
+        function nest(x) {
+            x = [x];
+        }
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Infinite type at 2:9: `...[]` (involving expressions at 2:9, 2:10)"
+    );
+}
+
+#[test]
+fn bad_equals_array_types() {
+    let err = check_source(&indoc! {"
+        let a = [1] == [2];
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Cannot compare values of type `number[]` at 1:9"
+    );
+}
+
+#[test]
+fn bad_jump_outside_loop() {
+    let err = check_source(&indoc! {"
+        while (true) {
+            function foo() {
+                continue;
+            }
+            break;
+        }
+        break;
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Cannot `continue` outside of a loop at 3:9
+            Cannot `break` outside of a loop at 7:1"}
+    );
 }
 
 #[test]
@@ -225,7 +1113,10 @@ fn bad_non_well_founded_type() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Conflicting type constraints");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Infinite type at 2:5: `(...) => a` (involving expressions at 2:5, 2:7)"
+    );
 }
 
 #[test]
@@ -237,7 +1128,10 @@ fn bad_type_usage_function() {
         }
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Conflicting type constraints");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 1:17: `number` and `string` (involving expressions at 1:17, 3:21)"
+    );
 }
 
 #[test]
@@ -259,5 +1153,90 @@ fn bad_nested_function_in_equals() {
         foo(1, 'xyz');
     "})
     .unwrap_err();
-    assert_eq!(&err.to_string(), "Conflicting type constraints");
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 10:5: `number` and `string` (involving expressions at 10:5, 10:8)"
+    );
+}
+
+#[test]
+fn bad_undetermined_let_type() {
+    let err = check_source(&indoc! {"
+        function foo() {
+            return foo();
+        }
+        let x = foo();
+    "})
+    .unwrap_err();
+    assert_eq!(&display_diagnostics(&err), "Undetermined type at 4:9");
+}
+
+#[test]
+fn bad_function_arity() {
+    let err = check_source(&indoc! {"
+        function foo(x: number): number {
+            return x;
+        }
+        foo(1, 2);
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 4:1: `() => number` and `(number, number) => number`"
+    );
+}
+
+#[test]
+fn bad_multiple_errors() {
+    // The conflicts in the first and the last line both equate `number` and `string` and are thus
+    // reported together.
+    let err = check_source(&indoc! {"
+        let x: number = 'abc';
+        let y = z;
+        let x = true;
+        let w: string = 5;
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        indoc! {"
+            Conflicting type constraints at 1:17: `number` and `string` (involving expressions at 1:17, 4:17)
+            Usage of undeclared variable `z` at 2:9
+            Variable `x` declared more than once at 3:1, previously declared at 1:1"}
+    );
+}
+
+#[test]
+fn type_aliases() {
+    let (p, lits, _, _) = check_source(&indoc! {"
+        type Endo = (x: number) => number;
+        function twice(f: Endo): Endo {
+            return function g(x: number) { return f(f(x)); };
+        }
+        function inc(x: number) { return x + 1; }
+        let n = twice(inc)(1);
+        function norm(point) {
+            type Point = { x: number; y: number };
+            let q: Point = point;
+            return q.x * q.x + q.y * q.y;
+        }
+        let m = norm({ x: 3, y: 4 });
+    "})
+    .unwrap();
+    let number_type = p.number_type().unwrap();
+    assert!(var_has_type("n", number_type, &p, &lits));
+    assert!(var_has_type("m", number_type, &p, &lits));
+}
+
+#[test]
+fn bad_type_alias_mismatch() {
+    let err = check_source(&indoc! {"
+        type Name = string;
+        let n: Name = 5;
+    "})
+    .unwrap_err();
+    assert_eq!(
+        &display_diagnostics(&err),
+        "Conflicting type constraints at 2:15: `number` and `string`"
+    );
 }